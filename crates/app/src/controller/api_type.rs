@@ -1,18 +1,138 @@
-use serde::{Deserialize, Serialize};
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::Value;
-use utoipa::ToSchema;
-use validator::Validate;
+use utoipa::{IntoParams, ToSchema};
+use validator::{Validate, ValidateArgs, ValidationErrors};
+
+use super::AppError;
+use crate::service::{
+    deletion::Dependents,
+    query::{ListQuery, SortOrder},
+};
 
 const SUCCESS_CODE: i32 = 0;
 const USERNAME_NOT_FOUND_CODE: i32 = -1;
 const WRONG_PASSWORD_CODE: i32 = -2;
+const NOT_LOGGED_IN_CODE: i32 = -3;
+const TOKEN_EXPIRED_CODE: i32 = -4;
+const USER_DISABLED_CODE: i32 = -5;
+const USER_LOCKED_CODE: i32 = -6;
+const USER_PENDING_CODE: i32 = -7;
+const TOKEN_REUSED_CODE: i32 = -8;
+const INVALID_CREDENTIALS_CODE: i32 = -9;
+const TOO_MANY_ATTEMPTS_CODE: i32 = -10;
+const CHALLENGE_REQUIRED_CODE: i32 = -11;
+const RESET_TOKEN_INVALID_CODE: i32 = -12;
+
+// 以下错误码与 HTTP 状态码一一对应，由 `AppError` 和 `error_middleware` 使用
+
+/// 请求参数不合法，包括请求体无法解析和校验失败（400）
+pub const INVALID_PARAMS_CODE: i32 = -13;
+/// 没有访问该接口的权限（403）
+pub const FORBIDDEN_CODE: i32 = -14;
+/// 记录不存在（404）
+pub const NOT_FOUND_CODE: i32 = -15;
+/// 与已有记录冲突，例如违反唯一约束（409）
+pub const CONFLICT_CODE: i32 = -16;
+/// 引用的记录不存在或仍被其他记录引用，例如违反外键约束（422）
+pub const REFERENCE_VIOLATION_CODE: i32 = -17;
+/// 服务端内部错误，详细信息只写入日志（500）
+pub const INTERNAL_ERROR_CODE: i32 = -18;
+
+/// 没有携带错误码的错误响应（例如请求体无法解析）按状态码补上错误码
+pub fn code_for_status(status: StatusCode) -> i32 {
+    match status {
+        StatusCode::UNAUTHORIZED => NOT_LOGGED_IN_CODE,
+        StatusCode::FORBIDDEN => FORBIDDEN_CODE,
+        StatusCode::NOT_FOUND => NOT_FOUND_CODE,
+        StatusCode::CONFLICT => CONFLICT_CODE,
+        StatusCode::TOO_MANY_REQUESTS => TOO_MANY_ATTEMPTS_CODE,
+        status if status.is_client_error() => INVALID_PARAMS_CODE,
+        _ => INTERNAL_ERROR_CODE,
+    }
+}
+
+/// REST 列表接口的查询参数，含义与 [`ListQuery`] 相同；
+/// 查询字符串无法表达嵌套的结构，`filter` 为 JSON 编码的过滤字段，例如 `{"status":0}`
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListParams {
+    #[serde(default = "default_page")]
+    pub page: u64,
+    #[serde(default = "default_page_size")]
+    pub page_size: u64,
+    pub keyword: Option<String>,
+    pub filter: Option<String>,
+    pub sort: Option<String>,
+    #[serde(default)]
+    #[param(inline)]
+    pub order: SortOrder,
+    pub cursor: Option<String>,
+}
+
+fn default_page() -> u64 {
+    1
+}
+
+fn default_page_size() -> u64 {
+    20
+}
+
+impl ListParams {
+    pub fn into_query<F: DeserializeOwned + Default>(self) -> Result<ListQuery<F>, AppError> {
+        let filter = match self.filter.as_deref().filter(|filter| !filter.is_empty()) {
+            Some(filter) => serde_json::from_str(filter)
+                .map_err(|e| AppError::bad_request(format!("invalid filter: {}", e)))?,
+            None => F::default(),
+        };
+        let query = ListQuery {
+            page: self.page,
+            page_size: self.page_size,
+            keyword: self.keyword,
+            filter,
+            sort: self.sort,
+            order: self.order,
+            cursor: self.cursor,
+        };
+        query
+            .validate()
+            .map_err(|e| AppError::bad_request(e.to_string()))?;
+        Ok(query)
+    }
+}
+
+/// 删除和彻底删除接口的查询参数
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteParams {
+    /// 仍关联该记录的数据如何处理：`block` 拒绝删除，`cascade`（默认）一起处理，`detach` 解除关联
+    #[serde(default)]
+    #[param(inline)]
+    pub dependents: Dependents,
+}
 
-#[derive(Deserialize, ToSchema, Validate)]
+#[derive(Deserialize, ToSchema)]
 pub struct ApiRequest<T> {
     pub id: Value,
     pub params: T,
 }
 
+/// 校验规则都在 `params` 上，`id` 不需要校验
+impl<T: Validate> Validate for ApiRequest<T> {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        self.params.validate()
+    }
+}
+
+/// 需要运行时配置（例如密码策略）的校验，配合 `axum_valid::ValidEx` 使用
+impl<'v, T: ValidateArgs<'v>> ValidateArgs<'v> for ApiRequest<T> {
+    type Args = T::Args;
+
+    fn validate_with_args(&self, args: Self::Args) -> Result<(), ValidationErrors> {
+        self.params.validate_with_args(args)
+    }
+}
+
 #[derive(Serialize, ToSchema, Validate)]
 pub struct ApiResponse<T> {
     pub id: Value,
@@ -24,6 +144,15 @@ pub struct ApiResponse<T> {
 }
 
 impl ApiResponse<String> {
+    pub fn new_error(id: Value, code: i32, message: String) -> Self {
+        Self {
+            id,
+            code,
+            data: None,
+            error: Some(message),
+        }
+    }
+
     pub fn new_success_without_data(id: Value) -> Self {
         Self {
             id,
@@ -44,16 +173,6 @@ impl<T> ApiResponse<T> {
         }
     }
 
-    #[allow(dead_code)]
-    pub fn new_error(id: Value, code: i32, message: String) -> Self {
-        Self {
-            id,
-            code,
-            data: None,
-            error: Some(message),
-        }
-    }
-
     pub fn username_not_found(id: Value) -> Self {
         Self {
             id,
@@ -71,4 +190,102 @@ impl<T> ApiResponse<T> {
             error: Some(String::from("Wrong password")),
         }
     }
+
+    pub fn not_logged_in(id: Value) -> Self {
+        Self {
+            id,
+            code: NOT_LOGGED_IN_CODE,
+            data: None,
+            error: Some(String::from("Not logged in")),
+        }
+    }
+
+    pub fn token_expired(id: Value) -> Self {
+        Self {
+            id,
+            code: TOKEN_EXPIRED_CODE,
+            data: None,
+            error: Some(String::from("Token expired")),
+        }
+    }
+
+    pub fn user_disabled(id: Value) -> Self {
+        Self {
+            id,
+            code: USER_DISABLED_CODE,
+            data: None,
+            error: Some(String::from("User disabled")),
+        }
+    }
+
+    pub fn user_locked(id: Value) -> Self {
+        Self {
+            id,
+            code: USER_LOCKED_CODE,
+            data: None,
+            error: Some(String::from("User locked")),
+        }
+    }
+
+    pub fn user_pending(id: Value) -> Self {
+        Self {
+            id,
+            code: USER_PENDING_CODE,
+            data: None,
+            error: Some(String::from("User pending activation")),
+        }
+    }
+
+    /// 已轮换的刷新令牌被再次使用，同一次登录签发的令牌都已失效
+    pub fn token_reused(id: Value) -> Self {
+        Self {
+            id,
+            code: TOKEN_REUSED_CODE,
+            data: None,
+            error: Some(String::from("Refresh token reused")),
+        }
+    }
+
+    /// 不区分用户名不存在和密码错误
+    pub fn invalid_credentials(id: Value) -> Self {
+        Self {
+            id,
+            code: INVALID_CREDENTIALS_CODE,
+            data: None,
+            error: Some(String::from("Invalid username or password")),
+        }
+    }
+
+    /// 连续登录失败过多，需要等待 `retry_after` 秒
+    pub fn too_many_attempts(id: Value, retry_after: u64) -> Self {
+        Self {
+            id,
+            code: TOO_MANY_ATTEMPTS_CODE,
+            data: None,
+            error: Some(format!(
+                "Too many failed attempts, retry after {} seconds",
+                retry_after
+            )),
+        }
+    }
+
+    /// 需要先通过 `/auth/challenge` 获取验证码，登录时带上答案
+    pub fn challenge_required(id: Value) -> Self {
+        Self {
+            id,
+            code: CHALLENGE_REQUIRED_CODE,
+            data: None,
+            error: Some(String::from("Challenge required")),
+        }
+    }
+
+    /// 重置令牌不存在、已过期或已使用
+    pub fn reset_token_invalid(id: Value) -> Self {
+        Self {
+            id,
+            code: RESET_TOKEN_INVALID_CODE,
+            data: None,
+            error: Some(String::from("Invalid or expired reset token")),
+        }
+    }
 }
@@ -0,0 +1,116 @@
+mod types;
+use types::{ListRequest, ListResponse};
+
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{
+    Json,
+    extract::{ConnectInfo, FromRequestParts, State},
+    http::{header, request::Parts},
+    middleware,
+};
+use axum_valid::Valid;
+use sea_orm::ConnectionTrait;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use super::{
+    AUDIT_TAG, AppError,
+    api_type::{ApiRequest, ApiResponse},
+    middleware::{Identity, RequestId, auth_middleware, require},
+};
+use crate::{
+    service::audit::{self, Context, Entry},
+    web_state::WebState,
+};
+
+/// 当前请求的审计上下文：登录用户、客户端 IP、User-Agent 和请求 id
+#[derive(Debug, Clone, Default)]
+pub struct Audit(pub Context);
+
+impl<S: Send + Sync> FromRequestParts<S> for Audit {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        // 同时支持测试中的 `MockConnectInfo`
+        let ip = ConnectInfo::<SocketAddr>::from_request_parts(parts, state)
+            .await
+            .ok()
+            .map(|ConnectInfo(addr)| addr.ip().to_string());
+        let extensions = &parts.extensions;
+        Ok(Self(Context {
+            actor_id: extensions
+                .get::<Identity>()
+                .map(|identity| identity.user_id),
+            ip,
+            user_agent: parts
+                .headers
+                .get(header::USER_AGENT)
+                .and_then(|agent| agent.to_str().ok())
+                .map(|agent| agent.chars().take(255).collect()),
+            request_id: extensions.get::<RequestId>().map(|id| id.0.clone()),
+        }))
+    }
+}
+
+impl Audit {
+    /// 登录成功等请求开始时还没有登录身份的事件，由调用方指定操作人
+    pub fn with_actor(&self, actor_id: i64) -> Self {
+        Self(Context {
+            actor_id: Some(actor_id),
+            ..self.0.clone()
+        })
+    }
+
+    /// 写入失败只记录日志，不影响已经完成的操作
+    pub async fn record<C: ConnectionTrait>(&self, db: &C, entry: Entry) {
+        let action = entry.action;
+        if let Err(e) = audit::record(db, &self.0, entry).await {
+            tracing::error!("record audit `{}` error: {:#}", action, e);
+        }
+    }
+}
+
+#[utoipa::path(
+  post,
+  path = "/audit/list",
+  request_body(content = ApiRequest<ListRequest>, content_type = "application/json"),
+  responses((status = OK, body = ApiResponse<ListResponse>,content_type = "application/json", description = "list audit logs")),
+  tag = AUDIT_TAG,
+  security(
+    ("Bearer" = [])
+  )
+)]
+pub async fn audit_list<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Valid(Json(request)): Valid<Json<ApiRequest<ListRequest>>>,
+) -> Result<Json<ApiResponse<ListResponse>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let page = audit::list(&state.db, &request.params).await?;
+
+    let response = ApiResponse::new_success(
+        request.id,
+        ListResponse {
+            logs: page.items.into_iter().map(|log| log.into()).collect(),
+            total: page.total,
+            page: page.page,
+            page_size: page.page_size,
+            next_cursor: page.next_cursor,
+        },
+    );
+    Ok(Json(response))
+}
+
+pub fn router<C>(state: Arc<WebState<C>>) -> OpenApiRouter
+where
+    C: ConnectionTrait + Send + Sync + 'static,
+{
+    OpenApiRouter::new()
+        .routes(require(routes!(audit_list), "audit:list"))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ))
+        .with_state(state)
+}
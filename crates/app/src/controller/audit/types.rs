@@ -0,0 +1,60 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use utoipa::ToSchema;
+
+use crate::{
+    entity::AuditLogModel,
+    service::{audit::AuditFilter, query::ListQuery},
+};
+
+/// 关键字、过滤和排序字段见 [`audit::list`](crate::service::audit::list)
+pub type ListRequest = ListQuery<AuditFilter>;
+
+#[derive(Serialize, ToSchema)]
+pub struct AuditLog {
+    pub id: i64,
+    /// 操作人，未登录时为空
+    pub actor_id: Option<i64>,
+    pub action: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    /// 变化前的字段，只包含发生变化的字段
+    pub before: Option<Value>,
+    /// 变化后的字段，或事件的附加信息
+    pub after: Option<Value>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+impl From<AuditLogModel> for AuditLog {
+    fn from(log: AuditLogModel) -> Self {
+        AuditLog {
+            id: log.id,
+            actor_id: log.actor_id,
+            action: log.action,
+            target_type: log.target_type,
+            target_id: log.target_id,
+            before: log.before,
+            after: log.after,
+            ip: log.ip,
+            user_agent: log.user_agent,
+            request_id: log.request_id,
+            created_at: log.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ListResponse {
+    pub logs: Vec<AuditLog>,
+    /// 满足条件的总数，按游标分页时不统计
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    pub page: u64,
+    pub page_size: u64,
+    /// 下一页的游标，只在按游标分页且还有数据时返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
@@ -1,57 +1,214 @@
 mod types;
-use types::{LoginReqest, LoginResponse, RegisterRequest, RegisterResponse};
+use types::{
+    ChallengeResponse, ChangePasswordRequest, ChangePasswordResponse, ForgotPasswordRequest,
+    LoginReqest, LogoutAllResponse, MeResponse, RefreshRequest, RegisterRequest,
+    ResetPasswordRequest, TokenResponse,
+};
 
-use std::sync::Arc;
+use std::{net::SocketAddr, sync::Arc};
 
-use axum::{Json, extract::State, http::StatusCode};
-use sea_orm::ConnectionTrait;
+use axum::{
+    Extension, Json,
+    extract::{ConnectInfo, State},
+    middleware,
+};
+use axum_valid::{Valid, ValidEx};
+use sea_orm::{ConnectionTrait, TransactionTrait};
+use serde_json::{Value, json};
 use utoipa_axum::{router::OpenApiRouter, routes};
 
 use super::{
-    AUTH_TAG,
+    AUTH_TAG, AppError, Audit,
     api_type::{ApiRequest, ApiResponse},
+    middleware::{Credential, Identity, auth_middleware, session_middleware},
+    user::types::User,
 };
 use crate::{
-    service::{online, user},
+    entity::UserModel,
+    service::{
+        audit::Entry,
+        data_scope::DataScope,
+        login_guard::Verdict,
+        menu,
+        notifier::Message,
+        online::{self, Current, Refresh},
+        password_policy::not_username,
+        password_reset,
+        permission::Permissions,
+        user::{self, Profile},
+        user_role,
+    },
     web_state::WebState,
 };
 
+/// 启用访问令牌时签发访问令牌和新一组刷新令牌，否则创建数据库会话
+async fn sign_in<C>(state: &WebState<C>, user: &UserModel) -> anyhow::Result<TokenResponse>
+where
+    C: ConnectionTrait,
+{
+    let Some(jwt) = &state.jwt else {
+        let online = online::create(&state.db, &state.session, user.id).await?;
+        return Ok(TokenResponse {
+            token: online.token,
+            refresh_token: None,
+            expires_in: None,
+        });
+    };
+
+    let refresh = online::create_refresh(&state.db, &state.session, user.id).await?;
+    let family = refresh.family.unwrap_or_default();
+    Ok(TokenResponse {
+        token: jwt.issue(user.id, user.perm_version, &family)?,
+        refresh_token: Some(refresh.token),
+        expires_in: Some(jwt.access_ttl),
+    })
+}
+
+/// 记录一次失败的登录，`reason` 与返回的错误对应，用户存在时以其为目标
+async fn login_failed<C>(
+    state: &WebState<C>,
+    audit: &Audit,
+    username: &str,
+    user_id: Option<i64>,
+    reason: &str,
+) where
+    C: ConnectionTrait,
+{
+    let mut entry = Entry::new("auth.login_failed");
+    if let Some(user_id) = user_id {
+        entry = entry.target("user", user_id);
+    }
+    let entry = entry.detail(json!({ "username": username, "reason": reason }));
+    audit.record(&state.db, entry).await;
+}
+
 #[utoipa::path(
   post,
   path = "/auth/login",
   request_body(content = ApiRequest<LoginReqest>, content_type = "application/json"),
-  responses((status = OK, body = ApiResponse<LoginResponse>,content_type = "application/json", description = "Login success")),
+  responses((status = OK, body = ApiResponse<TokenResponse>,content_type = "application/json", description = "Login success")),
   tag = AUTH_TAG
 )]
 pub async fn auth_login<C>(
     State(state): State<Arc<WebState<C>>>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    audit: Audit,
     Json(request): Json<ApiRequest<LoginReqest>>,
-) -> Result<Json<ApiResponse<LoginResponse>>, (StatusCode, String)>
+) -> Result<Json<ApiResponse<TokenResponse>>, AppError>
 where
     C: ConnectionTrait,
 {
-    let user = user::get_by_username(&state.db, &request.params.username)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let guard = &state.login_guard;
+    let username = &request.params.username;
+    let ip = connect_info.map(|Extension(ConnectInfo(addr))| addr.ip());
 
-    let response = if let Some(user) = user {
-        if user.password != request.params.password {
-            ApiResponse::wrong_password(request.id)
-        } else {
-            let online = online::create(&state.db, user.id)
-                .await
-                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-            ApiResponse::new_success(
+    match guard.check(username, ip) {
+        Verdict::Allowed { challenge: false } => {}
+        Verdict::Allowed { challenge: true } => {
+            // 没有配置验证码时只依靠退避和锁定
+            if let Some(verifier) = guard.challenge() {
+                let passed = request
+                    .params
+                    .challenge
+                    .as_ref()
+                    .is_some_and(|answer| verifier.verify(&answer.id, &answer.answer));
+                if !passed {
+                    login_failed(&state, &audit, username, None, "challenge_required").await;
+                    return Ok(Json(ApiResponse::challenge_required(request.id)));
+                }
+            }
+        }
+        Verdict::Throttled { retry_after } | Verdict::Locked { retry_after } => {
+            login_failed(&state, &audit, username, None, "too_many_attempts").await;
+            return Ok(Json(ApiResponse::too_many_attempts(
                 request.id,
-                LoginResponse {
-                    token: online.token,
-                },
-            )
+                retry_after.as_secs_f64().ceil() as u64,
+            )));
         }
-    } else {
-        ApiResponse::username_not_found(request.id)
+    }
+
+    let user = user::get_by_username(&state.db, username).await?;
+
+    let Some(user) = user else {
+        // 计算一次哈希，使用户名不存在时的耗时与校验密码相当
+        let _ = state.hasher.hash(&request.params.password);
+        guard.record_failure(username, ip);
+        login_failed(&state, &audit, username, None, "username_not_found").await;
+        let response = if guard.config().generic_error {
+            ApiResponse::invalid_credentials(request.id)
+        } else {
+            ApiResponse::username_not_found(request.id)
+        };
+        return Ok(Json(response));
+    };
+
+    if !user::verify_password(
+        &state.db,
+        state.hasher.as_ref(),
+        &user,
+        &request.params.password,
+    )
+    .await?
+    {
+        guard.record_failure(username, ip);
+        login_failed(&state, &audit, username, Some(user.id), "wrong_password").await;
+        let response = if guard.config().generic_error {
+            ApiResponse::invalid_credentials(request.id)
+        } else {
+            ApiResponse::wrong_password(request.id)
+        };
+        return Ok(Json(response));
+    }
+    guard.record_success(username);
+
+    // 只有正常状态可以登录，未知的状态按停用处理
+    if user.status != user::STATUS_ACTIVE {
+        let (reason, response) = match user.status {
+            user::STATUS_LOCKED => ("user_locked", ApiResponse::user_locked(request.id)),
+            user::STATUS_PENDING => ("user_pending", ApiResponse::user_pending(request.id)),
+            _ => ("user_disabled", ApiResponse::user_disabled(request.id)),
+        };
+        login_failed(&state, &audit, username, Some(user.id), reason).await;
+        return Ok(Json(response));
+    }
+
+    let tokens = sign_in(&state, &user).await?;
+    user::record_login(&state.db, user.id).await?;
+    audit
+        .with_actor(user.id)
+        .record(&state.db, Entry::new("auth.login").target("user", user.id))
+        .await;
+
+    let response = ApiResponse::new_success(request.id, tokens);
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/challenge",
+    request_body(content = ApiRequest<Value>, content_type = "application/json"),
+    responses((status = OK, body = ApiResponse<ChallengeResponse>,content_type = "application/json", description = "Issue a login challenge")),
+    tag = AUTH_TAG
+)]
+pub async fn auth_challenge<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Json(request): Json<ApiRequest<Value>>,
+) -> Result<Json<ApiResponse<ChallengeResponse>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let Some(verifier) = state.login_guard.challenge() else {
+        return Err(AppError::not_found("Login challenge is not enabled"));
     };
 
+    let challenge = verifier.issue();
+    let response = ApiResponse::new_success(
+        request.id,
+        ChallengeResponse {
+            id: challenge.id,
+            prompt: challenge.prompt,
+        },
+    );
     Ok(Json(response))
 }
 
@@ -59,41 +216,389 @@ where
     post,
     path = "/auth/register",
     request_body(content = ApiRequest<RegisterRequest>, content_type = "application/json"),
-    responses((status = OK, body = ApiResponse<RegisterResponse>,content_type = "application/json", description = "Register success")),
+    responses((status = OK, body = ApiResponse<TokenResponse>,content_type = "application/json", description = "Register success")),
     tag = AUTH_TAG
   )]
 pub async fn auth_register<C>(
     State(state): State<Arc<WebState<C>>>,
-    Json(request): Json<ApiRequest<RegisterRequest>>,
-) -> Result<Json<ApiResponse<RegisterResponse>>, (StatusCode, String)>
+    audit: Audit,
+    ValidEx(Json(request)): ValidEx<Json<ApiRequest<RegisterRequest>>>,
+) -> Result<Json<ApiResponse<TokenResponse>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let params = request.params;
+    let user = user::create_with_profile(
+        &state.db,
+        state.hasher.as_ref(),
+        &params.username,
+        &params.password,
+        Profile {
+            email: params.email,
+            phone: params.phone,
+            nickname: params.nickname,
+            avatar: params.avatar,
+        },
+    )
+    .await?;
+    let tokens = sign_in(&state, &user).await?;
+    audit
+        .with_actor(user.id)
+        .record(
+            &state.db,
+            Entry::new("auth.register")
+                .target("user", user.id)
+                .created(&User::from(user)),
+        )
+        .await;
+    let response = ApiResponse::new_success(request.id, tokens);
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/refresh",
+    request_body(content = ApiRequest<RefreshRequest>, content_type = "application/json"),
+    responses((status = OK, body = ApiResponse<TokenResponse>,content_type = "application/json", description = "Rotate refresh token and issue a new access token")),
+    tag = AUTH_TAG
+)]
+pub async fn auth_refresh<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Json(request): Json<ApiRequest<RefreshRequest>>,
+) -> Result<Json<ApiResponse<TokenResponse>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let Some(jwt) = &state.jwt else {
+        return Err(AppError::not_found("Access tokens are not enabled"));
+    };
+
+    let refresh = online::rotate(&state.db, &state.session, &request.params.refresh_token).await?;
+    let refresh = match refresh {
+        Refresh::Rotated(refresh) => refresh,
+        Refresh::Reused { user_id } => {
+            tracing::warn!(
+                "refresh token reused for user {}, token family revoked",
+                user_id
+            );
+            return Ok(Json(ApiResponse::token_reused(request.id)));
+        }
+        Refresh::Expired => return Ok(Json(ApiResponse::token_expired(request.id))),
+        Refresh::NotFound => return Ok(Json(ApiResponse::not_logged_in(request.id))),
+    };
+
+    let user = user::get(&state.db, &DataScope::all(), refresh.user_id).await?;
+    let Some(user) = user.filter(|user| user.status == user::STATUS_ACTIVE) else {
+        return Ok(Json(ApiResponse::not_logged_in(request.id)));
+    };
+
+    let family = refresh.family.unwrap_or_default();
+    let token = jwt.issue(user.id, user.perm_version, &family)?;
+    let response = ApiResponse::new_success(
+        request.id,
+        TokenResponse {
+            token,
+            refresh_token: Some(refresh.token),
+            expires_in: Some(jwt.access_ttl),
+        },
+    );
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    responses((status = OK, body = ApiResponse<String>,content_type = "application/json", description = "Logout current session")),
+    tag = AUTH_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn auth_logout<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(identity): Extension<Identity>,
+    audit: Audit,
+) -> Result<Json<ApiResponse<String>>, AppError>
 where
     C: ConnectionTrait,
 {
-    if user::get_by_username(&state.db, &request.params.username)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .is_some()
+    // 访问令牌无法撤销，删除刷新令牌后到期即失效
+    match &identity.credential {
+        Credential::Session(token) => {
+            online::delete(&state.db, token).await?;
+            state.permission_cache.invalidate_token(token);
+        }
+        Credential::Access { family } => {
+            online::delete_family(&state.db, family).await?;
+        }
+    }
+    audit
+        .record(
+            &state.db,
+            Entry::new("auth.logout").target("user", identity.user_id),
+        )
+        .await;
+
+    let response = ApiResponse::new_success_without_data(Value::Null);
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/logout_all",
+    responses((status = OK, body = ApiResponse<LogoutAllResponse>,content_type = "application/json", description = "Logout all sessions of current user")),
+    tag = AUTH_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn auth_logout_all<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(identity): Extension<Identity>,
+    audit: Audit,
+) -> Result<Json<ApiResponse<LogoutAllResponse>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let count = online::delete_by_user(&state.db, identity.user_id).await?;
+    state.permission_cache.invalidate_user(identity.user_id);
+    audit
+        .record(
+            &state.db,
+            Entry::new("auth.logout_all")
+                .target("user", identity.user_id)
+                .detail(json!({ "count": count })),
+        )
+        .await;
+
+    let response = ApiResponse::new_success(Value::Null, LogoutAllResponse { count });
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/change_password",
+    request_body(content = ApiRequest<ChangePasswordRequest>, content_type = "application/json"),
+    responses((status = OK, body = ApiResponse<ChangePasswordResponse>,content_type = "application/json", description = "Change own password and revoke other sessions")),
+    tag = AUTH_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn auth_change_password<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(identity): Extension<Identity>,
+    audit: Audit,
+    ValidEx(Json(request)): ValidEx<Json<ApiRequest<ChangePasswordRequest>>>,
+) -> Result<Json<ApiResponse<ChangePasswordResponse>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let user = user::get(&state.db, &DataScope::all(), identity.user_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("User not found"))?;
+
+    // 旧密码的尝试次数与登录共用限制，防止持有会话的人猜测密码
+    if let Verdict::Throttled { retry_after } | Verdict::Locked { retry_after } =
+        state.login_guard.check(&user.name, None)
     {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            "Username already exists".to_string(),
-        ));
+        return Ok(Json(ApiResponse::too_many_attempts(
+            request.id,
+            retry_after.as_secs_f64().ceil() as u64,
+        )));
     }
+    if !user::verify_password(
+        &state.db,
+        state.hasher.as_ref(),
+        &user,
+        &request.params.old_password,
+    )
+    .await?
+    {
+        state.login_guard.record_failure(&user.name, None);
+        return Ok(Json(ApiResponse::wrong_password(request.id)));
+    }
+    not_username(&user.name, &request.params.new_password)
+        .map_err(|e| AppError::bad_request(e.to_string()))?;
 
-    let user = user::create(
+    user::change_password(
         &state.db,
-        &request.params.username,
-        &request.params.password,
+        state.hasher.as_ref(),
+        &user,
+        &request.params.new_password,
+        state.password_policy.history,
     )
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-    let online = online::create(&state.db, user.id)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    .await?;
+
+    let current = match &identity.credential {
+        Credential::Session(token) => Current::Session(token),
+        Credential::Access { family } => Current::Family(family),
+    };
+    let revoked = online::delete_others(&state.db, user.id, current).await?;
+    state.permission_cache.invalidate_user(user.id);
+    audit
+        .record(
+            &state.db,
+            Entry::new("auth.change_password")
+                .target("user", user.id)
+                .detail(json!({ "revoked": revoked })),
+        )
+        .await;
+
+    // 修改密码使权限版本递增，当前的访问令牌随之失效
+    let token = match (&state.jwt, &identity.credential) {
+        (Some(jwt), Credential::Access { family }) => {
+            let user = user::get(&state.db, &DataScope::all(), user.id)
+                .await?
+                .ok_or_else(|| AppError::not_found("User not found"))?;
+            let token = jwt.issue(user.id, user.perm_version, family)?;
+            Some(token)
+        }
+        _ => None,
+    };
+
+    let response = ApiResponse::new_success(request.id, ChangePasswordResponse { revoked, token });
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/forgot_password",
+    request_body(content = ApiRequest<ForgotPasswordRequest>, content_type = "application/json"),
+    responses((status = OK, body = ApiResponse<String>,content_type = "application/json", description = "Send a password reset token to the email if it belongs to an active user")),
+    tag = AUTH_TAG
+)]
+pub async fn auth_forgot_password<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Valid(Json(request)): Valid<Json<ApiRequest<ForgotPasswordRequest>>>,
+) -> Result<Json<ApiResponse<String>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let user = user::get_by_email(&state.db, &request.params.email).await?;
+
+    // 无论邮箱是否存在都返回成功，避免被用来探测邮箱
+    if let Some(user) = user.filter(|user| user.status == user::STATUS_ACTIVE) {
+        let token = password_reset::create(&state.db, &state.password_reset, user.id).await?;
+        let config = &state.password_reset;
+        let instruction = match &config.link {
+            Some(link) => format!("open {}", link.replace("{token}", &token)),
+            None => format!("use the reset token {}", token),
+        };
+        let message = Message {
+            to: request.params.email.clone(),
+            subject: "Password reset".to_string(),
+            body: format!(
+                "Hi {},\n\nTo reset your password, {} within {} minutes.\n\
+                 If you did not request a password reset, ignore this message.",
+                user.name,
+                instruction,
+                config.ttl.div_ceil(60)
+            ),
+        };
+        // 在后台发送，邮箱是否存在时的响应时间相同
+        let notifier = state.notifier.clone();
+        let user_id = user.id;
+        tokio::spawn(async move {
+            if let Err(e) = notifier.send(&message).await {
+                tracing::error!("send password reset to user {} error: {}", user_id, e);
+            }
+        });
+    }
+
+    let response = ApiResponse::new_success_without_data(request.id);
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/reset_password",
+    request_body(content = ApiRequest<ResetPasswordRequest>, content_type = "application/json"),
+    responses((status = OK, body = ApiResponse<String>,content_type = "application/json", description = "Reset password with a reset token and revoke all sessions")),
+    tag = AUTH_TAG
+)]
+pub async fn auth_reset_password<C>(
+    State(state): State<Arc<WebState<C>>>,
+    audit: Audit,
+    ValidEx(Json(request)): ValidEx<Json<ApiRequest<ResetPasswordRequest>>>,
+) -> Result<Json<ApiResponse<String>>, AppError>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    // 新密码不符合要求时回滚，令牌仍然可以再次使用
+    let txn = state.db.begin().await?;
+
+    let user_id = password_reset::consume(&txn, &request.params.token).await?;
+    let user = match user_id {
+        Some(user_id) => user::get(&txn, &DataScope::all(), user_id).await?,
+        None => None,
+    };
+    let Some(user) = user.filter(|user| user.status == user::STATUS_ACTIVE) else {
+        return Ok(Json(ApiResponse::reset_token_invalid(request.id)));
+    };
+
+    not_username(&user.name, &request.params.new_password)
+        .map_err(|e| AppError::bad_request(e.to_string()))?;
+    user::change_password(
+        &txn,
+        state.hasher.as_ref(),
+        &user,
+        &request.params.new_password,
+        state.password_policy.history,
+    )
+    .await?;
+    online::delete_by_user(&txn, user.id).await?;
+
+    txn.commit().await?;
+    state.permission_cache.invalidate_user(user.id);
+    // 找回密码后解除登录失败造成的锁定
+    state.login_guard.record_success(&user.name);
+    audit
+        .with_actor(user.id)
+        .record(
+            &state.db,
+            Entry::new("auth.reset_password").target("user", user.id),
+        )
+        .await;
+
+    let response = ApiResponse::new_success_without_data(request.id);
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/me",
+    responses((status = OK, body = ApiResponse<MeResponse>,content_type = "application/json", description = "Current user, roles, menus and permissions")),
+    tag = AUTH_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn auth_me<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(identity): Extension<Identity>,
+    Extension(permissions): Extension<Permissions>,
+) -> Result<Json<ApiResponse<MeResponse>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let user = user::get(&state.db, &DataScope::all(), identity.user_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("User not found"))?;
+    let roles = user_role::list(&state.db, user.id).await?;
+    let menus = menu::tree(&state.db, (!permissions.is_admin).then_some(user.id), true).await?;
+
+    let mut codes: Vec<String> = permissions.codes.into_iter().collect();
+    codes.sort_unstable();
+
     let response = ApiResponse::new_success(
-        request.id,
-        RegisterResponse {
-            token: online.token,
+        Value::Null,
+        MeResponse {
+            user: user.into(),
+            roles: roles.into_iter().map(|role| role.into()).collect(),
+            menus: menus.into_iter().map(|node| node.into()).collect(),
+            is_admin: permissions.is_admin,
+            permissions: codes,
         },
     );
     Ok(Json(response))
@@ -101,10 +606,34 @@ where
 
 pub fn router<C>(state: Arc<WebState<C>>) -> OpenApiRouter
 where
-    C: ConnectionTrait + Send + Sync + 'static,
+    C: ConnectionTrait + TransactionTrait + Send + Sync + 'static,
 {
+    let session_router = OpenApiRouter::new()
+        .routes(routes!(auth_logout))
+        .routes(routes!(auth_logout_all))
+        .routes(routes!(auth_change_password))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            session_middleware,
+        ));
+
+    // 需要加载权限，但不要求任何权限码
+    let auth_router =
+        OpenApiRouter::new()
+            .routes(routes!(auth_me))
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth_middleware,
+            ));
+
     OpenApiRouter::new()
         .routes(routes!(auth_login))
+        .routes(routes!(auth_challenge))
         .routes(routes!(auth_register))
+        .routes(routes!(auth_refresh))
+        .routes(routes!(auth_forgot_password))
+        .routes(routes!(auth_reset_password))
+        .merge(session_router)
+        .merge(auth_router)
         .with_state(state)
 }
@@ -1,24 +1,126 @@
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+
+use crate::{
+    controller::{
+        menu::types::MenuTree,
+        user::types::{Role, User, validate_phone},
+    },
+    service::password_policy::{PasswordPolicy, not_username, validate_password},
+};
 
 #[derive(Deserialize, ToSchema)]
 pub struct LoginReqest {
     pub username: String,
     pub password: String,
+    /// 连续失败后要求的验证码答案
+    #[serde(default)]
+    pub challenge: Option<ChallengeAnswer>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ChallengeAnswer {
+    pub id: String,
+    pub answer: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ChallengeResponse {
+    pub id: String,
+    /// 展示给用户的题目，第三方验证码服务可能为空
+    pub prompt: String,
 }
 
+/// 未启用访问令牌时 `token` 是数据库会话令牌，不返回刷新令牌
 #[derive(Serialize, ToSchema)]
-pub struct LoginResponse {
+pub struct TokenResponse {
     pub token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// 访问令牌的有效期（秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_in: Option<u64>,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
+#[validate(context = PasswordPolicy)]
+#[validate(schema(function = "register_password_not_username"))]
 pub struct RegisterRequest {
     pub username: String,
+    #[validate(custom(function = "validate_password", use_context))]
     pub password: String,
+    /// 找回密码使用的邮箱
+    #[serde(default)]
+    #[validate(email)]
+    pub email: Option<String>,
+    #[serde(default)]
+    #[validate(custom(function = "validate_phone"))]
+    pub phone: Option<String>,
+    /// 显示名称
+    #[serde(default)]
+    #[validate(length(max = 50))]
+    pub nickname: Option<String>,
+    /// 头像地址
+    #[serde(default)]
+    #[validate(url, length(max = 255))]
+    pub avatar: Option<String>,
 }
 
-#[derive(Serialize, ToSchema)]
-pub struct RegisterResponse {
+fn register_password_not_username(request: &RegisterRequest) -> Result<(), ValidationError> {
+    not_username(&request.username, &request.password)
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+#[validate(context = PasswordPolicy)]
+pub struct ChangePasswordRequest {
+    pub old_password: String,
+    #[validate(custom(function = "validate_password", use_context))]
+    pub new_password: String,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct ForgotPasswordRequest {
+    #[validate(email)]
+    pub email: String,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+#[validate(context = PasswordPolicy)]
+pub struct ResetPasswordRequest {
+    /// 邮件中收到的重置令牌
     pub token: String,
+    #[validate(custom(function = "validate_password", use_context))]
+    pub new_password: String,
+}
+
+/// 使用访问令牌时旧的访问令牌随密码修改失效，`token` 是重新签发的访问令牌
+#[derive(Serialize, ToSchema)]
+pub struct ChangePasswordResponse {
+    /// 注销的其他会话数
+    pub revoked: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LogoutAllResponse {
+    pub count: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MeResponse {
+    pub user: User,
+    pub roles: Vec<Role>,
+    /// 当前用户可见的菜单树
+    pub menus: Vec<MenuTree>,
+    /// 为真时拥有所有权限，`permissions` 为空
+    pub is_admin: bool,
+    /// 接口鉴权使用的权限码，按字典序排列
+    pub permissions: Vec<String>,
 }
@@ -0,0 +1,68 @@
+use sea_orm::ConnectionTrait;
+
+use super::Audit;
+use crate::{
+    service::{cache::PermissionCache, data_scope::DataScope, permission},
+    web_state::WebState,
+};
+
+/// 调用成功后需要失效的权限缓存
+#[derive(Debug, Clone, Copy)]
+pub enum Invalidation {
+    User(i64),
+    Role(i32),
+    All,
+}
+
+impl Invalidation {
+    pub fn apply(self, cache: &PermissionCache) {
+        match self {
+            Self::User(id) => cache.invalidate_user(id),
+            Self::Role(id) => cache.invalidate_role(id),
+            Self::All => cache.clear(),
+        }
+    }
+}
+
+/// 一次调用的上下文，原有接口、REST 接口和 RPC 方法共用同一套处理函数；
+/// `db` 为数据库连接或 RPC 整批共用的事务
+pub struct Call<'a, C: ConnectionTrait, D> {
+    pub state: &'a WebState<C>,
+    pub db: &'a D,
+    pub scope: &'a DataScope,
+    /// 审计日志与调用写入同一个连接或事务，事务回滚时一起回滚
+    pub audit: &'a Audit,
+    /// 开启事务时在提交后才失效
+    pub invalidations: Vec<Invalidation>,
+}
+
+impl<C: ConnectionTrait, D: ConnectionTrait> Call<'_, C, D> {
+    /// 发起调用的用户是否为超级管理员
+    pub async fn is_superuser(&self) -> anyhow::Result<bool> {
+        match self.audit.0.actor_id {
+            Some(actor_id) => {
+                permission::is_superuser(self.db, &self.state.permission.superuser, actor_id).await
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+impl<'a, C: ConnectionTrait> Call<'a, C, C> {
+    /// 直接使用 `state.db`，处理完后调用 [`Call::finish`] 使缓存失效
+    pub fn new(state: &'a WebState<C>, scope: &'a DataScope, audit: &'a Audit) -> Self {
+        Self {
+            state,
+            db: &state.db,
+            scope,
+            audit,
+            invalidations: Vec::new(),
+        }
+    }
+
+    pub fn finish(self) {
+        for invalidation in self.invalidations {
+            invalidation.apply(&self.state.permission_cache);
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use axum::{
+    Json,
+    body::{Body, to_bytes},
+    http::{Request, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sea_orm::{DbErr, SqlErr};
+use serde_json::Value;
+
+use super::api_type::{
+    ApiResponse, CONFLICT_CODE, FORBIDDEN_CODE, INTERNAL_ERROR_CODE, INVALID_PARAMS_CODE,
+    NOT_FOUND_CODE, REFERENCE_VIOLATION_CODE, code_for_status,
+};
+use crate::service::error::{
+    Conflict, HasDependents, InvalidCursor, InvalidSort, MissingReferences, ParentCycle,
+    PasswordReused,
+};
+
+/// 接口的错误，渲染为带错误码的 `ApiResponse`，请求的 `id` 由 [`error_middleware`] 填入
+#[derive(Debug)]
+pub enum AppError {
+    /// 请求参数不合法（400）
+    BadRequest(String),
+    /// 没有权限（403）
+    Forbidden(String),
+    /// 记录不存在（404）
+    NotFound(String),
+    /// 与已有记录冲突（409）
+    Conflict(String),
+    /// 引用的记录不存在或仍被引用（422）
+    ReferenceViolation(String),
+    /// 内部错误，只把详细信息写入日志（500）
+    Internal(anyhow::Error),
+}
+
+impl AppError {
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::BadRequest(message.into())
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::NotFound(message.into())
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::ReferenceViolation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> i32 {
+        match self {
+            Self::BadRequest(_) => INVALID_PARAMS_CODE,
+            Self::Forbidden(_) => FORBIDDEN_CODE,
+            Self::NotFound(_) => NOT_FOUND_CODE,
+            Self::Conflict(_) => CONFLICT_CODE,
+            Self::ReferenceViolation(_) => REFERENCE_VIOLATION_CODE,
+            Self::Internal(_) => INTERNAL_ERROR_CODE,
+        }
+    }
+
+    /// 错误码和返回给客户端的描述，内部错误的详细信息在这里写入日志
+    pub fn report(self) -> (i32, String) {
+        let code = self.code();
+        let message = match self {
+            Self::BadRequest(message)
+            | Self::Forbidden(message)
+            | Self::NotFound(message)
+            | Self::Conflict(message)
+            | Self::ReferenceViolation(message) => message,
+            Self::Internal(e) => {
+                tracing::error!("internal error: {:#}", e);
+                "Internal server error".to_string()
+            }
+        };
+        (code, message)
+    }
+}
+
+/// 服务层的类型化错误按类型转换，数据库错误按 [`DbErr`] 的种类转换，其余的都是内部错误
+impl From<anyhow::Error> for AppError {
+    fn from(e: anyhow::Error) -> Self {
+        if let Some(conflict) = e.downcast_ref::<Conflict>() {
+            return Self::Conflict(conflict.to_string());
+        }
+        if let Some(missing) = e.downcast_ref::<MissingReferences>() {
+            return Self::ReferenceViolation(missing.to_string());
+        }
+        if let Some(dependents) = e.downcast_ref::<HasDependents>() {
+            return Self::ReferenceViolation(dependents.to_string());
+        }
+        if e.is::<PasswordReused>()
+            || e.is::<ParentCycle>()
+            || e.is::<InvalidSort>()
+            || e.is::<InvalidCursor>()
+        {
+            return Self::BadRequest(e.to_string());
+        }
+        match e.downcast_ref::<DbErr>() {
+            Some(DbErr::RecordNotFound(_) | DbErr::RecordNotUpdated) => {
+                Self::NotFound("Record not found".to_string())
+            }
+            Some(db) => match db.sql_err() {
+                Some(SqlErr::UniqueConstraintViolation(_)) => {
+                    Self::Conflict("Record already exists".to_string())
+                }
+                Some(SqlErr::ForeignKeyConstraintViolation(_)) => Self::ReferenceViolation(
+                    "Referenced record does not exist or is still referenced".to_string(),
+                ),
+                _ => Self::Internal(e),
+            },
+            None => Self::Internal(e),
+        }
+    }
+}
+
+impl From<DbErr> for AppError {
+    fn from(e: DbErr) -> Self {
+        anyhow::Error::from(e).into()
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let (code, message) = self.report();
+        let response = ApiResponse::new_error(Value::Null, code, message);
+        (status, Json(response)).into_response()
+    }
+}
+
+/// 请求体的大小上限，与 axum 提取器的默认值相同，由 `router` 通过 `DefaultBodyLimit` 应用到提取器
+pub const BODY_LIMIT: usize = 2 * 1024 * 1024;
+
+/// 把所有错误响应统一为 `ApiResponse`：填入请求体中的 `id`，
+/// 没有错误码的响应（例如请求体无法解析、校验失败）按状态码补上错误码
+pub async fn error_middleware(request: Request<Body>, next: Next) -> Response {
+    let (parts, body) = request.into_parts();
+    // 读取请求体时同样受大小限制，超出时不再交给后续的处理
+    let Ok(bytes) = to_bytes(body, BODY_LIMIT).await else {
+        let status = StatusCode::PAYLOAD_TOO_LARGE;
+        let response = ApiResponse::new_error(
+            Value::Null,
+            code_for_status(status),
+            "Request body too large".to_string(),
+        );
+        return (status, Json(response)).into_response();
+    };
+    let id = serde_json::from_slice::<Value>(&bytes)
+        .ok()
+        .and_then(|body| body.get("id").cloned())
+        .unwrap_or(Value::Null);
+    let response = next
+        .run(Request::from_parts(parts, Body::from(bytes)))
+        .await;
+
+    let status = response.status();
+    if !status.is_client_error() && !status.is_server_error() {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = to_bytes(body, usize::MAX).await.unwrap_or_default();
+    let mut body = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(body) if body.get("code").is_some() => body,
+        _ => {
+            let message = String::from_utf8_lossy(&bytes).into_owned();
+            let message = if message.is_empty() {
+                status.canonical_reason().unwrap_or_default().to_string()
+            } else {
+                message
+            };
+            serde_json::to_value(ApiResponse::new_error(
+                Value::Null,
+                code_for_status(status),
+                message,
+            ))
+            .unwrap_or_default()
+        }
+    };
+    if body.get("id").is_none_or(Value::is_null) {
+        body["id"] = id;
+    }
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("application/json"),
+    );
+    Response::from_parts(parts, Body::from(body.to_string()))
+}
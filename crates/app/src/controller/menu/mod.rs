@@ -1,25 +1,37 @@
-mod types;
-use types::{CreateRequest, GetResponse, ListRequest, ListResponse, UpdateRequest};
+pub(super) mod ops;
+mod rest;
+pub(super) mod types;
+use types::{
+    CreateRequest, GetResponse, ListRequest, ListResponse, TreeRequest, TreeResponse, UpdateRequest,
+};
 
 use std::sync::Arc;
 
 use axum::{
-    Json,
-    extract::{Path, State},
-    http::StatusCode,
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::Uri,
     middleware,
 };
 use axum_valid::Valid;
-use sea_orm::ConnectionTrait;
+use sea_orm::{ConnectionTrait, TransactionTrait};
 use serde_json::Value;
 use utoipa_axum::{router::OpenApiRouter, routes};
 
 use super::{
-    MENU_TAG,
-    api_type::{ApiRequest, ApiResponse},
-    middleware::auth_middleware,
+    AppError, Audit, MENU_TAG,
+    api_type::{ApiRequest, ApiResponse, DeleteParams},
+    call::Call,
+    middleware::{Identity, auth_middleware, require},
+};
+use crate::{
+    service::{
+        data_scope::DataScope,
+        menu,
+        permission::{PermissionConfig, Permissions},
+    },
+    web_state::WebState,
 };
-use crate::{service::menu, web_state::WebState};
 
 #[utoipa::path(
   post,
@@ -33,21 +45,18 @@ use crate::{service::menu, web_state::WebState};
 )]
 pub async fn menu_list<C>(
     State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
     Valid(Json(request)): Valid<Json<ApiRequest<ListRequest>>>,
-) -> Result<Json<ApiResponse<ListResponse>>, (StatusCode, String)>
+) -> Result<Json<ApiResponse<ListResponse>>, AppError>
 where
     C: ConnectionTrait,
 {
-    let menus = menu::list(&state.db, request.params.page, request.params.page_size)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let mut call = Call::new(&state, &scope, &audit);
+    let data = ops::list(&mut call, request.params).await?;
+    call.finish();
 
-    let response = ApiResponse::new_success(
-        request.id,
-        ListResponse {
-            menus: menus.into_iter().map(|menu| menu.into()).collect(),
-        },
-    );
+    let response = ApiResponse::new_success(request.id, data);
     Ok(Json(response))
 }
 
@@ -63,19 +72,16 @@ where
 )]
 pub async fn menu_create<C>(
     State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
     Valid(Json(request)): Valid<Json<ApiRequest<CreateRequest>>>,
-) -> Result<Json<ApiResponse<String>>, (StatusCode, String)>
+) -> Result<Json<ApiResponse<String>>, AppError>
 where
     C: ConnectionTrait,
 {
-    menu::create(
-        &state.db,
-        &request.params.name,
-        &request.params.path,
-        request.params.is_frame,
-    )
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::create(&mut call, request.params).await?;
+    call.finish();
 
     let response = ApiResponse::new_success(request.id, "success".to_string());
     Ok(Json(response))
@@ -84,7 +90,8 @@ where
 #[utoipa::path(
     get,
     path = "/menu/delete/{id}",
-    responses((status = OK, body = ApiResponse<String>,content_type = "application/json", description = "delete user")),
+    params(DeleteParams),
+    responses((status = OK, body = ApiResponse<String>,content_type = "application/json", description = "soft delete menu")),
     tag = MENU_TAG,
     security(
         ("Bearer" = [])
@@ -92,14 +99,71 @@ where
 )]
 pub async fn menu_delete<C>(
     State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
     Path(id): Path<i32>,
-) -> Result<Json<ApiResponse<String>>, (StatusCode, String)>
+    Query(params): Query<DeleteParams>,
+) -> Result<Json<ApiResponse<String>>, AppError>
 where
-    C: ConnectionTrait,
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::delete(&mut call, id, params.dependents).await?;
+    call.finish();
+
+    let response = ApiResponse::new_success(Value::Number(id.into()), "success".to_string());
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/menu/restore/{id}",
+    responses((status = OK, body = ApiResponse<String>,content_type = "application/json", description = "restore soft deleted menu")),
+    tag = MENU_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn menu_restore<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<String>>, AppError>
+where
+    C: ConnectionTrait + TransactionTrait,
 {
-    menu::delete(&state.db, id)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::restore(&mut call, id).await?;
+    call.finish();
+
+    let response = ApiResponse::new_success(Value::Number(id.into()), "success".to_string());
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/menu/purge/{id}",
+    params(DeleteParams),
+    responses((status = OK, body = ApiResponse<String>,content_type = "application/json", description = "permanently delete soft deleted menu")),
+    tag = MENU_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn menu_purge<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Path(id): Path<i32>,
+    Query(params): Query<DeleteParams>,
+) -> Result<Json<ApiResponse<String>>, AppError>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::purge(&mut call, id, params.dependents).await?;
+    call.finish();
 
     let response = ApiResponse::new_success(Value::Number(id.into()), "success".to_string());
     Ok(Json(response))
@@ -117,20 +181,17 @@ where
 )]
 pub async fn menu_update<C>(
     State(state): State<Arc<WebState<C>>>,
-    Json(request): Json<ApiRequest<UpdateRequest>>,
-) -> Result<Json<ApiResponse<String>>, (StatusCode, String)>
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Valid(Json(request)): Valid<Json<ApiRequest<UpdateRequest>>>,
+) -> Result<Json<ApiResponse<String>>, AppError>
 where
     C: ConnectionTrait,
 {
-    menu::update(
-        &state.db,
-        request.params.id,
-        request.params.name,
-        request.params.path,
-        request.params.is_frame,
-    )
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let mut call = Call::new(&state, &scope, &audit);
+    let UpdateRequest { id, fields } = request.params;
+    ops::update(&mut call, id, fields).await?;
+    call.finish();
 
     let response = ApiResponse::new_success(request.id, "success".to_string());
     Ok(Json(response))
@@ -147,34 +208,82 @@ where
 )]
 pub async fn menu_get<C>(
     State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
     Path(id): Path<i32>,
-) -> Result<Json<ApiResponse<GetResponse>>, (StatusCode, String)>
+) -> Result<Json<ApiResponse<GetResponse>>, AppError>
 where
     C: ConnectionTrait,
 {
-    let menu = menu::get(&state.db, id)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    if let Some(menu) = menu {
-        let response =
-            ApiResponse::new_success(Value::Number(id.into()), GetResponse { menu: menu.into() });
-        Ok(Json(response))
+    let mut call = Call::new(&state, &scope, &audit);
+    let data = ops::get(&mut call, id).await?;
+    call.finish();
+
+    let response = ApiResponse::new_success(Value::Number(id.into()), data);
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/menu/tree",
+    request_body(content = ApiRequest<TreeRequest>, content_type = "application/json"),
+    responses((status = OK, body = ApiResponse<TreeResponse>,content_type = "application/json", description = "menu tree")),
+    tag = MENU_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn menu_tree<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(identity): Extension<Identity>,
+    Extension(config): Extension<PermissionConfig>,
+    Extension(permissions): Extension<Permissions>,
+    uri: Uri,
+    Json(request): Json<ApiRequest<TreeRequest>>,
+) -> Result<Json<ApiResponse<TreeResponse>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let user_id = if request.params.mine {
+        (!permissions.is_admin).then_some(identity.user_id)
+    } else if permissions.allows(&config, "menu:list", uri.path()) {
+        None
     } else {
-        Err((StatusCode::NOT_FOUND, "Menu not found".to_string()))
-    }
+        return Err(AppError::Forbidden("No permission".to_string()));
+    };
+
+    let menus = menu::tree(&state.db, user_id, request.params.mine).await?;
+
+    let response = ApiResponse::new_success(
+        request.id,
+        TreeResponse {
+            menus: menus.into_iter().map(|node| node.into()).collect(),
+        },
+    );
+    Ok(Json(response))
 }
 
 pub fn router<C>(state: Arc<WebState<C>>) -> OpenApiRouter
 where
-    C: ConnectionTrait + Send + Sync + 'static,
+    C: ConnectionTrait + TransactionTrait + Send + Sync + 'static,
 {
     OpenApiRouter::new()
-        .routes(routes!(menu_list))
-        .routes(routes!(menu_create))
-        .routes(routes!(menu_delete))
-        .routes(routes!(menu_update))
-        .routes(routes!(menu_get))
+        .routes(require(routes!(menu_list), "menu:list"))
+        .routes(require(routes!(menu_create), "menu:create"))
+        .routes(require(routes!(menu_delete), "menu:delete"))
+        .routes(require(routes!(menu_restore), "menu:delete"))
+        .routes(require(routes!(menu_purge), "menu:purge"))
+        .routes(require(routes!(menu_update), "menu:update"))
+        .routes(require(routes!(menu_get), "menu:get"))
+        .routes(require(routes!(rest::menus_list), "menu:list"))
+        .routes(require(routes!(rest::menus_get), "menu:get"))
+        .routes(require(routes!(rest::menus_create), "menu:create"))
+        .routes(require(routes!(rest::menus_update), "menu:update"))
+        .routes(require(routes!(rest::menus_delete), "menu:delete"))
+        .routes(require(routes!(rest::menus_restore), "menu:delete"))
+        .routes(require(routes!(rest::menus_purge), "menu:purge"))
+        // 登录即可获取自己的菜单树，整棵树在处理函数中检查 `menu:list`
+        .routes(routes!(menu_tree))
         .layer(middleware::from_fn_with_state(
             state.clone(),
             auth_middleware,
@@ -0,0 +1,197 @@
+//! `/menu/*`、`/menus` 和 `menu.*` 共用的处理，接口只负责提取参数和组织响应
+
+use sea_orm::{ConnectionTrait, TransactionTrait};
+
+use super::types::{CreateRequest, GetResponse, ListRequest, ListResponse, Menu, UpdateFields};
+use crate::{
+    controller::{
+        AppError,
+        call::{Call, Invalidation},
+    },
+    entity::MenuModel,
+    service::{
+        audit::Entry,
+        deletion::Dependents,
+        menu::{self, Layout, LayoutUpdate},
+    },
+};
+
+pub async fn list<C, D>(
+    call: &mut Call<'_, C, D>,
+    params: ListRequest,
+) -> Result<ListResponse, AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait,
+{
+    let page = menu::list(call.db, &params).await?;
+    Ok(ListResponse {
+        menus: page.items.into_iter().map(|menu| menu.into()).collect(),
+        total: page.total,
+        page: page.page,
+        page_size: page.page_size,
+        next_cursor: page.next_cursor,
+    })
+}
+
+pub async fn get<C, D>(call: &mut Call<'_, C, D>, id: i32) -> Result<GetResponse, AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait,
+{
+    let menu = find(call, id).await?;
+    Ok(GetResponse { menu: menu.into() })
+}
+
+pub async fn create<C, D>(
+    call: &mut Call<'_, C, D>,
+    params: CreateRequest,
+) -> Result<Menu, AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait,
+{
+    let menu = menu::create(
+        call.db,
+        &params.name,
+        &params.path,
+        &params.perms,
+        params.is_frame,
+        Layout {
+            parent_id: params.parent_id,
+            sort_order: params.sort_order,
+            icon: params.icon,
+            menu_type: params.menu_type,
+            visible: params.visible,
+        },
+    )
+    .await?;
+    let menu = Menu::from(menu);
+    call.audit
+        .record(
+            call.db,
+            Entry::new("menu.create")
+                .target("menu", menu.id)
+                .created(&menu),
+        )
+        .await;
+    Ok(menu)
+}
+
+pub async fn update<C, D>(
+    call: &mut Call<'_, C, D>,
+    id: i32,
+    params: UpdateFields,
+) -> Result<(), AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait,
+{
+    let before = find(call, id).await?;
+    let invalidate = params.perms.is_some() || params.path.is_some();
+    menu::update(
+        call.db,
+        id,
+        params.name,
+        params.path,
+        params.perms,
+        params.is_frame,
+        LayoutUpdate {
+            parent_id: params.parent_id.map(|id| Some(id).filter(|id| *id != 0)),
+            sort_order: params.sort_order,
+            icon: params.icon,
+            menu_type: params.menu_type,
+            visible: params.visible,
+        },
+    )
+    .await?;
+    if invalidate {
+        call.invalidations.push(Invalidation::All);
+    }
+    if let Some(after) = menu::get(call.db, id).await? {
+        call.audit
+            .record(
+                call.db,
+                Entry::new("menu.update")
+                    .target("menu", id)
+                    .changed(&Menu::from(before), &Menu::from(after)),
+            )
+            .await;
+    }
+    Ok(())
+}
+
+pub async fn delete<C, D>(
+    call: &mut Call<'_, C, D>,
+    id: i32,
+    dependents: Dependents,
+) -> Result<(), AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait + TransactionTrait,
+{
+    let menu = find(call, id).await?;
+    menu::delete(call.db, id, dependents).await?;
+    call.invalidations.push(Invalidation::All);
+    call.audit
+        .record(
+            call.db,
+            Entry::new("menu.delete")
+                .target("menu", id)
+                .deleted(&Menu::from(menu)),
+        )
+        .await;
+    Ok(())
+}
+
+pub async fn restore<C, D>(call: &mut Call<'_, C, D>, id: i32) -> Result<(), AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait + TransactionTrait,
+{
+    if menu::get_deleted(call.db, id).await?.is_none() {
+        return Err(AppError::not_found("Deleted menu not found"));
+    }
+    menu::restore(call.db, id).await?;
+    call.invalidations.push(Invalidation::All);
+    call.audit
+        .record(call.db, Entry::new("menu.restore").target("menu", id))
+        .await;
+    Ok(())
+}
+
+pub async fn purge<C, D>(
+    call: &mut Call<'_, C, D>,
+    id: i32,
+    dependents: Dependents,
+) -> Result<(), AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait + TransactionTrait,
+{
+    let menu = menu::get_deleted(call.db, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Deleted menu not found"))?;
+    menu::purge(call.db, id, dependents).await?;
+    call.invalidations.push(Invalidation::All);
+    call.audit
+        .record(
+            call.db,
+            Entry::new("menu.purge")
+                .target("menu", id)
+                .deleted(&Menu::from(menu)),
+        )
+        .await;
+    Ok(())
+}
+
+/// 只查找未删除的菜单
+async fn find<C, D>(call: &Call<'_, C, D>, id: i32) -> Result<MenuModel, AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait,
+{
+    menu::get(call.db, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Menu not found"))
+}
@@ -0,0 +1,236 @@
+use std::sync::Arc;
+
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use axum_valid::Valid;
+use sea_orm::{ConnectionTrait, TransactionTrait};
+use serde_json::Value;
+
+use super::{
+    ops,
+    types::{CreateRequest, GetResponse, ListResponse, UpdateFields},
+};
+use crate::{
+    controller::{
+        AppError, Audit, MENU_TAG,
+        api_type::{ApiResponse, DeleteParams, ListParams},
+        call::Call,
+    },
+    service::data_scope::DataScope,
+    web_state::WebState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/menus",
+    params(ListParams),
+    responses((status = OK, body = ApiResponse<ListResponse>, content_type = "application/json", description = "list menus")),
+    tag = MENU_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn menus_list<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Query(params): Query<ListParams>,
+) -> Result<Json<ApiResponse<ListResponse>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    let data = ops::list(&mut call, params.into_query()?).await?;
+    call.finish();
+
+    let response = ApiResponse::new_success(Value::Null, data);
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/menus/{id}",
+    responses(
+        (status = OK, body = ApiResponse<GetResponse>, content_type = "application/json", description = "get menu"),
+        (status = NOT_FOUND, description = "menu not found")
+    ),
+    tag = MENU_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn menus_get<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<GetResponse>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    let data = ops::get(&mut call, id).await?;
+    call.finish();
+
+    let response = ApiResponse::new_success(Value::Null, data);
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/menus",
+    request_body(content = CreateRequest, content_type = "application/json"),
+    responses(
+        (status = CREATED, body = ApiResponse<GetResponse>, content_type = "application/json", description = "create menu"),
+        (status = UNPROCESSABLE_ENTITY, description = "parent menu not found")
+    ),
+    tag = MENU_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn menus_create<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Valid(Json(params)): Valid<Json<CreateRequest>>,
+) -> Result<impl IntoResponse, AppError>
+where
+    C: ConnectionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    let menu = ops::create(&mut call, params).await?;
+    call.finish();
+
+    let location = format!("/menus/{}", menu.id);
+    let response = ApiResponse::new_success(Value::Null, GetResponse { menu });
+    Ok((
+        StatusCode::CREATED,
+        [(header::LOCATION, location)],
+        Json(response),
+    ))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/menus/{id}",
+    request_body(content = UpdateFields, content_type = "application/json"),
+    responses(
+        (status = NO_CONTENT, description = "update menu"),
+        (status = NOT_FOUND, description = "menu not found")
+    ),
+    tag = MENU_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn menus_update<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Path(id): Path<i32>,
+    Valid(Json(params)): Valid<Json<UpdateFields>>,
+) -> Result<StatusCode, AppError>
+where
+    C: ConnectionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::update(&mut call, id, params).await?;
+    call.finish();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/menus/{id}",
+    params(DeleteParams),
+    responses(
+        (status = NO_CONTENT, description = "soft delete menu"),
+        (status = NOT_FOUND, description = "menu not found"),
+        (status = UNPROCESSABLE_ENTITY, description = "menu still has roles or children and dependents is block")
+    ),
+    tag = MENU_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn menus_delete<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Path(id): Path<i32>,
+    Query(params): Query<DeleteParams>,
+) -> Result<StatusCode, AppError>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::delete(&mut call, id, params.dependents).await?;
+    call.finish();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/menus/{id}/restore",
+    responses(
+        (status = NO_CONTENT, description = "restore soft deleted menu"),
+        (status = NOT_FOUND, description = "deleted menu not found")
+    ),
+    tag = MENU_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn menus_restore<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, AppError>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::restore(&mut call, id).await?;
+    call.finish();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/menus/{id}/purge",
+    params(DeleteParams),
+    responses(
+        (status = NO_CONTENT, description = "permanently delete soft deleted menu"),
+        (status = NOT_FOUND, description = "deleted menu not found"),
+        (status = UNPROCESSABLE_ENTITY, description = "menu still has roles or children and dependents is block")
+    ),
+    tag = MENU_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn menus_purge<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Path(id): Path<i32>,
+    Query(params): Query<DeleteParams>,
+) -> Result<StatusCode, AppError>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::purge(&mut call, id, params.dependents).await?;
+    call.finish();
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -2,26 +2,29 @@ use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use validator::Validate;
 
-use crate::entity::MenuModel;
+use crate::{
+    entity::MenuModel,
+    service::{
+        menu::{self, MenuFilter, MenuNode},
+        query::ListQuery,
+    },
+};
 
-#[derive(Deserialize, ToSchema, Validate)]
-pub struct ListRequest {
-    #[validate(range(min = 1, message = "page must be greater than 0"))]
-    pub page: u64,
-    #[validate(range(
-        min = 1,
-        max = 100,
-        message = "page_size must be greater than 0 and less than 100"
-    ))]
-    pub page_size: u64,
-}
+/// 关键字、过滤和排序字段见 [`menu::list`](crate::service::menu::list)
+pub type ListRequest = ListQuery<MenuFilter>;
 
 #[derive(Serialize, ToSchema)]
 pub struct Menu {
     pub id: i32,
     pub name: String,
     pub path: String,
+    pub perms: String,
     pub is_frame: bool,
+    pub parent_id: Option<i32>,
+    pub sort_order: i32,
+    pub icon: String,
+    pub menu_type: i16,
+    pub visible: bool,
 }
 impl From<MenuModel> for Menu {
     fn from(menu: MenuModel) -> Self {
@@ -29,7 +32,13 @@ impl From<MenuModel> for Menu {
             id: menu.id,
             name: menu.name,
             path: menu.path,
+            perms: menu.perms,
             is_frame: menu.is_frame,
+            parent_id: menu.parent_id,
+            sort_order: menu.sort_order,
+            icon: menu.icon,
+            menu_type: menu.menu_type,
+            visible: menu.visible,
         }
     }
 }
@@ -37,32 +46,112 @@ impl From<MenuModel> for Menu {
 #[derive(Serialize, ToSchema)]
 pub struct ListResponse {
     pub menus: Vec<Menu>,
+    /// 满足条件的总数，按游标分页时不统计
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    pub page: u64,
+    pub page_size: u64,
+    /// 下一页的游标，只在按游标分页且还有数据时返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct CreateRequest {
     pub name: String,
     pub path: String,
+    /// 权限码，例如 `user:list`
+    #[serde(default)]
+    pub perms: String,
     pub is_frame: bool,
+    /// 上级菜单，为空时是顶层菜单
+    #[serde(default)]
+    pub parent_id: Option<i32>,
+    #[serde(default)]
+    pub sort_order: i32,
+    #[serde(default)]
+    pub icon: String,
+    /// 菜单类型（0目录 1页面 2按钮），默认为页面
+    #[serde(default = "default_menu_type")]
+    pub menu_type: i16,
+    #[serde(default = "default_visible")]
+    pub visible: bool,
 }
 
-#[derive(Serialize, ToSchema)]
-pub struct CreateResponse {
-    pub menu: Menu,
+fn default_menu_type() -> i16 {
+    menu::TYPE_PAGE
 }
 
-#[derive(Deserialize, ToSchema)]
+fn default_visible() -> bool {
+    true
+}
+
+/// `/menu/update` 和 `menu.update` 的参数
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct UpdateRequest {
     pub id: i32,
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub fields: UpdateFields,
+}
+
+/// 要修改的字段，`PATCH /menus/{id}` 的请求体，id 取自路径
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct UpdateFields {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub path: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub perms: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub is_frame: Option<bool>,
+    /// 上级菜单，`0` 表示移到顶层
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_order: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub menu_type: Option<i16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visible: Option<bool>,
 }
 
 #[derive(Serialize, ToSchema)]
 pub struct GetResponse {
     pub menu: Menu,
 }
+
+#[derive(Deserialize, ToSchema)]
+pub struct TreeRequest {
+    /// 只返回当前用户被授予且可见的菜单，否则返回整棵树（需要 `menu:list` 权限）
+    #[serde(default)]
+    pub mine: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MenuTree {
+    #[serde(flatten)]
+    pub menu: Menu,
+    #[schema(no_recursion)]
+    pub children: Vec<MenuTree>,
+}
+impl From<MenuNode> for MenuTree {
+    fn from(node: MenuNode) -> Self {
+        MenuTree {
+            menu: node.menu.into(),
+            children: node
+                .children
+                .into_iter()
+                .map(|child| child.into())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TreeResponse {
+    pub menus: Vec<MenuTree>,
+}
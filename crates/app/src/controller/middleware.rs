@@ -1,70 +1,273 @@
 use axum::{
+    Json,
     body::Body,
     extract::State,
-    http::{Request, StatusCode},
-    middleware::Next,
-    response::IntoResponse,
+    http::{HeaderValue, Request, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
 };
 use sea_orm::ConnectionTrait;
+use serde_json::Value;
 use std::sync::Arc;
+use utoipa_axum::router::UtoipaMethodRouter;
+use uuid::Uuid;
 
+use super::{AppError, api_type::ApiResponse};
 use crate::{
-    service::online::{get_menu_path_by_token, is_admin_by_token},
+    service::{
+        cache::CachedPermissions,
+        data_scope::{self, DataScope},
+        online::{self, Session},
+        permission::{self, PermissionConfig, Permissions},
+        token::{self, Access, JwtConfig},
+        user, user_role,
+    },
     web_state::WebState,
 };
 
 pub const AUTH_HEADER: &str = "Authorization";
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
 
+/// 当前请求的 id，写入审计日志并在响应头中返回
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// 沿用客户端或网关传入的 `x-request-id`（不超过 64 个可见字符），否则生成一个新的
+pub async fn request_id_middleware(mut request: Request<Body>, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty() && value.len() <= 64)
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    request
+        .extensions_mut()
+        .insert(RequestId(request_id.clone()));
+
+    let mut response = next.run(request).await;
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// 当前请求的登录身份
+#[derive(Debug, Clone)]
+pub struct Identity {
+    pub user_id: i64,
+    pub credential: Credential,
+}
+
+#[derive(Debug, Clone)]
+pub enum Credential {
+    /// 数据库中的会话令牌
+    Session(String),
+    /// 无状态的访问令牌，`family` 是与其一同签发的刷新令牌所属的组
+    Access { family: String },
+}
+
+/// 只校验登录状态，通过后把当前身份放入请求扩展，供 `Extension<Identity>` 提取
+pub async fn session_middleware<C>(
+    State(state): State<Arc<WebState<C>>>,
+    mut request: Request<Body>,
+    next: Next,
+) -> Result<Response, Response>
+where
+    C: ConnectionTrait,
+{
+    let Some(token) = bearer_token(&request) else {
+        return Err(unauthorized(ApiResponse::not_logged_in(Value::Null)));
+    };
+    let identity = authenticate(&state, &token).await?;
+    request.extensions_mut().insert(identity);
+    Ok(next.run(request).await)
+}
+
+/// 校验登录状态并加载当前会话的权限和数据范围（优先使用缓存），具体路由需要的权限码由 [`require`] 检查
 pub async fn auth_middleware<C>(
     State(state): State<Arc<WebState<C>>>,
-    request: Request<Body>,
+    mut request: Request<Body>,
     next: Next,
-) -> Result<impl IntoResponse, (StatusCode, String)>
+) -> Result<Response, Response>
 where
     C: ConnectionTrait,
 {
-    let token = request
-        .headers()
-        .get(AUTH_HEADER)
-        .ok_or_else(|| {
-            (
-                StatusCode::UNAUTHORIZED,
-                String::from("Authorization not found in header"),
-            )
-        })
-        .and_then(|v| {
-            v.to_str().map(String::from).map_err(|e| {
-                (
-                    StatusCode::BAD_REQUEST,
-                    format!("format Authorization error: {e}"),
-                )
-            })
-        })?;
+    let Some(token) = bearer_token(&request) else {
+        return Err(unauthorized(ApiResponse::not_logged_in(Value::Null)));
+    };
+    let identity = authenticate(&state, &token).await?;
+
+    let cached = match state.permission_cache.get(&token, identity.user_id) {
+        Some(cached) => cached,
+        None => {
+            let cached = load_permissions(&state, identity.user_id)
+                .await
+                .map_err(|e| AppError::Internal(e).into_response())?;
+            state.permission_cache.insert(&token, cached.clone());
+            cached
+        }
+    };
+
+    request.extensions_mut().insert(identity);
+    request.extensions_mut().insert(state.permission.clone());
+    request.extensions_mut().insert(cached.scope);
+    request.extensions_mut().insert(cached.permissions);
+
+    Ok(next.run(request).await)
+}
+
+async fn load_permissions<C>(state: &WebState<C>, user_id: i64) -> anyhow::Result<CachedPermissions>
+where
+    C: ConnectionTrait,
+{
+    let permissions = permission::load(&state.db, &state.permission, user_id).await?;
+    let scope = if permissions.is_admin {
+        DataScope::all()
+    } else {
+        data_scope::load(&state.db, user_id).await?
+    };
+    let role_ids = user_role::list(&state.db, user_id)
+        .await?
+        .into_iter()
+        .map(|role| role.id)
+        .collect();
 
+    Ok(CachedPermissions {
+        user_id,
+        role_ids,
+        permissions,
+        scope,
+    })
+}
+
+/// 给路由绑定需要的权限码，并写入 OpenAPI 的 `x-permission` 扩展
+///
+/// 必须位于 [`auth_middleware`] 之内
+pub fn require<S>(
+    (schemas, mut paths, method_router): UtoipaMethodRouter<S>,
+    code: &'static str,
+) -> UtoipaMethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    for item in paths.paths.values_mut() {
+        for operation in [
+            &mut item.get,
+            &mut item.put,
+            &mut item.post,
+            &mut item.delete,
+            &mut item.patch,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            operation
+                .extensions
+                .get_or_insert_with(Default::default)
+                .insert("x-permission".to_string(), code.into());
+        }
+    }
+
+    let method_router =
+        method_router.layer(middleware::from_fn_with_state(code, permission_middleware));
+    (schemas, paths, method_router)
+}
+
+async fn permission_middleware(
+    State(code): State<&'static str>,
+    request: Request<Body>,
+    next: Next,
+) -> Result<Response, Response> {
+    let (Some(config), Some(permissions)) = (
+        request.extensions().get::<PermissionConfig>(),
+        request.extensions().get::<Permissions>(),
+    ) else {
+        tracing::error!("permission `{}` checked outside auth_middleware", code);
+        return Err(AppError::Internal(anyhow::anyhow!("permissions not loaded")).into_response());
+    };
+
+    let uri = request.uri().path();
+    tracing::debug!(
+        "uri: {}, code: {}, permissions: {:?}",
+        uri,
+        code,
+        permissions
+    );
+
+    if !permissions.allows(config, code, uri) {
+        return Err(AppError::Forbidden("No permission".to_string()).into_response());
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// 缺少或无法解析 Authorization 头时返回 `None`
+fn bearer_token(request: &Request<Body>) -> Option<String> {
+    let token = request.headers().get(AUTH_HEADER)?.to_str().ok()?;
     let token = token.trim_start_matches("Bearer ");
     tracing::debug!("token: {}", token);
+    Some(token.to_string())
+}
+
+/// 启用访问令牌时按令牌格式区分访问令牌和会话令牌
+async fn authenticate<C>(state: &WebState<C>, token: &str) -> Result<Identity, Response>
+where
+    C: ConnectionTrait,
+{
+    if let Some(jwt) = state.jwt.as_ref().filter(|_| token::is_jwt(token)) {
+        return authenticate_access(state, jwt, token).await;
+    }
 
-    if is_admin_by_token(&state.db, token)
+    match online::validate(&state.db, &state.session, token)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| AppError::Internal(e).into_response())?
     {
-        tracing::debug!("is admin");
-        Ok(next.run(request).await)
-    } else {
-        let menus = get_menu_path_by_token(&state.db, token)
-            .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-        let uri = request.uri().path();
+        Session::Valid(online) => Ok(Identity {
+            user_id: online.user_id,
+            credential: Credential::Session(online.token),
+        }),
+        Session::Expired => Err(unauthorized(ApiResponse::token_expired(Value::Null))),
+        Session::NotFound => Err(unauthorized(ApiResponse::not_logged_in(Value::Null))),
+    }
+}
 
-        tracing::debug!("uri: {}, menus: {:?}", uri, menus);
+/// 校验签名和有效期后只查询一次用户，权限版本变化时要求客户端刷新令牌
+async fn authenticate_access<C>(
+    state: &WebState<C>,
+    jwt: &JwtConfig,
+    token: &str,
+) -> Result<Identity, Response>
+where
+    C: ConnectionTrait,
+{
+    let claims = match jwt.verify(token) {
+        Access::Valid(claims) => claims,
+        Access::Expired => return Err(unauthorized(ApiResponse::token_expired(Value::Null))),
+        Access::Invalid => return Err(unauthorized(ApiResponse::not_logged_in(Value::Null))),
+    };
+    let user_id = claims
+        .user_id()
+        .map_err(|_| unauthorized(ApiResponse::not_logged_in(Value::Null)))?;
 
-        if !menus.iter().any(|menu| uri.starts_with(menu)) {
-            return Err((StatusCode::FORBIDDEN, String::from("No permission")));
+    let user = user::get(&state.db, &DataScope::all(), user_id)
+        .await
+        .map_err(|e| AppError::Internal(e).into_response())?;
+    match user {
+        Some(user) if user.status != user::STATUS_ACTIVE => {
+            Err(unauthorized(ApiResponse::not_logged_in(Value::Null)))
         }
-
-        let response = next.run(request).await;
-
-        Ok(response)
+        Some(user) if user.perm_version != claims.pv => {
+            Err(unauthorized(ApiResponse::token_expired(Value::Null)))
+        }
+        Some(user) => Ok(Identity {
+            user_id: user.id,
+            credential: Credential::Access { family: claims.sid },
+        }),
+        None => Err(unauthorized(ApiResponse::not_logged_in(Value::Null))),
     }
 }
+
+fn unauthorized(response: ApiResponse<String>) -> Response {
+    (StatusCode::UNAUTHORIZED, Json(response)).into_response()
+}
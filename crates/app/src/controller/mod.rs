@@ -1,10 +1,18 @@
 mod api_type;
 
+mod error;
+pub use error::AppError;
+
 mod middleware;
 
+mod audit;
+pub use audit::Audit;
 mod auth;
+mod call;
 mod menu;
+mod online;
 mod role;
+mod rpc;
 mod user;
 
 mod router;
@@ -14,3 +22,6 @@ pub const AUTH_TAG: &str = "Auth";
 pub const USER_TAG: &str = "User";
 pub const ROLE_TAG: &str = "Role";
 pub const MENU_TAG: &str = "Menu";
+pub const ONLINE_TAG: &str = "Online";
+pub const RPC_TAG: &str = "RPC";
+pub const AUDIT_TAG: &str = "Audit";
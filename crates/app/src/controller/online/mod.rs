@@ -0,0 +1,196 @@
+mod rest;
+mod types;
+use types::{CacheStatsResponse, GetResponse, KickResponse, ListRequest, ListResponse};
+
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    middleware,
+};
+use axum_valid::Valid;
+use sea_orm::ConnectionTrait;
+use serde_json::{Value, json};
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use super::{
+    AppError, Audit, ONLINE_TAG,
+    api_type::{ApiRequest, ApiResponse},
+    middleware::{auth_middleware, require},
+};
+use crate::{
+    service::{audit::Entry, online},
+    web_state::WebState,
+};
+
+#[utoipa::path(
+  post,
+  path = "/online/list",
+  request_body(content = ApiRequest<ListRequest>, content_type = "application/json"),
+  responses((status = OK, body = ApiResponse<ListResponse>,content_type = "application/json", description = "list sessions")),
+  tag = ONLINE_TAG,
+  security(
+    ("Bearer" = [])
+  )
+)]
+pub async fn online_list<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Valid(Json(request)): Valid<Json<ApiRequest<ListRequest>>>,
+) -> Result<Json<ApiResponse<ListResponse>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let page = online::list(&state.db, &request.params).await?;
+
+    let response = ApiResponse::new_success(
+        request.id,
+        ListResponse {
+            sessions: page
+                .items
+                .into_iter()
+                .map(|session| session.into())
+                .collect(),
+            total: page.total,
+            page: page.page,
+            page_size: page.page_size,
+            next_cursor: page.next_cursor,
+        },
+    );
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/online/get/{id}",
+    responses((status = OK, body = ApiResponse<GetResponse>,content_type = "application/json", description = "get session")),
+    tag = ONLINE_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn online_get<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Path(id): Path<i64>,
+) -> Result<Json<ApiResponse<GetResponse>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let session = online::get_with_user(&state.db, id).await?;
+
+    if let Some(session) = session {
+        let response = ApiResponse::new_success(
+            Value::Number(id.into()),
+            GetResponse {
+                session: session.into(),
+            },
+        );
+        Ok(Json(response))
+    } else {
+        Err(AppError::not_found("Session not found"))
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/online/delete/{id}",
+    responses((status = OK, body = ApiResponse<String>,content_type = "application/json", description = "kick session")),
+    tag = ONLINE_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn online_delete<C>(
+    State(state): State<Arc<WebState<C>>>,
+    audit: Audit,
+    Path(id): Path<i64>,
+) -> Result<Json<ApiResponse<String>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let session = online::get_by_id(&state.db, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Session not found"))?;
+    online::delete(&state.db, &session.token).await?;
+    state.permission_cache.invalidate_token(&session.token);
+    audit
+        .record(
+            &state.db,
+            Entry::new("session.delete").target("user", session.user_id),
+        )
+        .await;
+
+    let response = ApiResponse::new_success_without_data(Value::Number(id.into()));
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/online/kick/{user_id}",
+    responses((status = OK, body = ApiResponse<KickResponse>,content_type = "application/json", description = "kick all sessions of user")),
+    tag = ONLINE_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn online_kick<C>(
+    State(state): State<Arc<WebState<C>>>,
+    audit: Audit,
+    Path(user_id): Path<i64>,
+) -> Result<Json<ApiResponse<KickResponse>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let count = online::delete_by_user(&state.db, user_id).await?;
+    state.permission_cache.invalidate_user(user_id);
+    audit
+        .record(
+            &state.db,
+            Entry::new("session.kick")
+                .target("user", user_id)
+                .detail(json!({ "count": count })),
+        )
+        .await;
+
+    let response = ApiResponse::new_success(Value::Number(user_id.into()), KickResponse { count });
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/online/cache_stats",
+    responses((status = OK, body = ApiResponse<CacheStatsResponse>,content_type = "application/json", description = "permission cache hit/miss counters")),
+    tag = ONLINE_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn online_cache_stats<C>(
+    State(state): State<Arc<WebState<C>>>,
+) -> Result<Json<ApiResponse<CacheStatsResponse>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let response = ApiResponse::new_success(Value::Null, state.permission_cache.stats().into());
+    Ok(Json(response))
+}
+
+pub fn router<C>(state: Arc<WebState<C>>) -> OpenApiRouter
+where
+    C: ConnectionTrait + Send + Sync + 'static,
+{
+    OpenApiRouter::new()
+        .routes(require(routes!(online_list), "online:list"))
+        .routes(require(routes!(online_get), "online:get"))
+        .routes(require(routes!(online_delete), "online:delete"))
+        .routes(require(routes!(online_kick), "online:kick"))
+        .routes(require(routes!(online_cache_stats), "online:list"))
+        .routes(require(routes!(rest::sessions_list), "online:list"))
+        .routes(require(routes!(rest::sessions_get), "online:get"))
+        .routes(require(routes!(rest::sessions_delete), "online:delete"))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ))
+        .with_state(state)
+}
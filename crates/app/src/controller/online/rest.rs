@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use sea_orm::ConnectionTrait;
+use serde_json::Value;
+
+use super::types::{GetResponse, ListResponse};
+use crate::{
+    controller::{
+        AppError, Audit, ONLINE_TAG,
+        api_type::{ApiResponse, ListParams},
+    },
+    service::{audit::Entry, online},
+    web_state::WebState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/sessions",
+    params(ListParams),
+    responses((status = OK, body = ApiResponse<ListResponse>, content_type = "application/json", description = "list sessions")),
+    tag = ONLINE_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn sessions_list<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Query(params): Query<ListParams>,
+) -> Result<Json<ApiResponse<ListResponse>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let page = online::list(&state.db, &params.into_query()?).await?;
+
+    let response = ApiResponse::new_success(
+        Value::Null,
+        ListResponse {
+            sessions: page
+                .items
+                .into_iter()
+                .map(|session| session.into())
+                .collect(),
+            total: page.total,
+            page: page.page,
+            page_size: page.page_size,
+            next_cursor: page.next_cursor,
+        },
+    );
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/{id}",
+    responses(
+        (status = OK, body = ApiResponse<GetResponse>, content_type = "application/json", description = "get session"),
+        (status = NOT_FOUND, description = "session not found")
+    ),
+    tag = ONLINE_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn sessions_get<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Path(id): Path<i64>,
+) -> Result<Json<ApiResponse<GetResponse>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let session = online::get_with_user(&state.db, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Session not found"))?;
+
+    let response = ApiResponse::new_success(
+        Value::Null,
+        GetResponse {
+            session: session.into(),
+        },
+    );
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/sessions/{id}",
+    responses(
+        (status = NO_CONTENT, description = "kick session"),
+        (status = NOT_FOUND, description = "session not found")
+    ),
+    tag = ONLINE_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn sessions_delete<C>(
+    State(state): State<Arc<WebState<C>>>,
+    audit: Audit,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, AppError>
+where
+    C: ConnectionTrait,
+{
+    let session = online::get_by_id(&state.db, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Session not found"))?;
+    online::delete(&state.db, &session.token).await?;
+    state.permission_cache.invalidate_token(&session.token);
+    audit
+        .record(
+            &state.db,
+            Entry::new("session.delete").target("user", session.user_id),
+        )
+        .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
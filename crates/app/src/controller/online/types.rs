@@ -0,0 +1,78 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    entity::{OnlineModel, UserModel},
+    service::{cache::CacheStats, online::OnlineFilter, query::ListQuery},
+};
+
+/// 关键字、过滤和排序字段见 [`online::list`](crate::service::online::list)
+pub type ListRequest = ListQuery<OnlineFilter>;
+
+#[derive(Serialize, ToSchema)]
+pub struct Online {
+    /// 会话编号，令牌本身不会返回
+    pub id: i64,
+    pub user_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+impl From<(OnlineModel, Option<UserModel>)> for Online {
+    fn from((online, user): (OnlineModel, Option<UserModel>)) -> Self {
+        Online {
+            id: online.id,
+            user_id: online.user_id,
+            username: user.map(|user| user.name),
+            created_at: online.created_at,
+            last_seen_at: online.last_seen_at,
+            expires_at: online.expires_at,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ListResponse {
+    pub sessions: Vec<Online>,
+    /// 满足条件的总数，按游标分页时不统计
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    pub page: u64,
+    pub page_size: u64,
+    /// 下一页的游标，只在按游标分页且还有数据时返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct GetResponse {
+    pub session: Online,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct KickResponse {
+    pub count: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CacheStatsResponse {
+    pub hits: u64,
+    pub misses: u64,
+    /// 当前缓存的令牌数
+    pub size: usize,
+    /// 为 0 时未启用缓存
+    pub capacity: usize,
+}
+impl From<CacheStats> for CacheStatsResponse {
+    fn from(stats: CacheStats) -> Self {
+        CacheStatsResponse {
+            hits: stats.hits,
+            misses: stats.misses,
+            size: stats.size,
+            capacity: stats.capacity,
+        }
+    }
+}
@@ -1,25 +1,33 @@
-mod types;
-use types::{CreateRequest, GetResponse, ListRequest, ListResponse, UpdateRequest};
+pub(super) mod ops;
+mod rest;
+pub(super) mod types;
+use types::{
+    AssignDeptsRequest, AssignMenusRequest, CreateRequest, DeptsResponse, GetResponse, ListRequest,
+    ListResponse, MenusResponse, UpdateRequest,
+};
 
 use std::sync::Arc;
 
 use axum::{
-    Json,
-    extract::{Path, State},
-    http::StatusCode,
+    Extension, Json,
+    extract::{Path, Query, State},
     middleware,
 };
 use axum_valid::Valid;
-use sea_orm::ConnectionTrait;
-use serde_json::Value;
+use sea_orm::{ConnectionTrait, TransactionTrait};
+use serde_json::{Value, json};
 use utoipa_axum::{router::OpenApiRouter, routes};
 
 use super::{
-    ROLE_TAG,
-    api_type::{ApiRequest, ApiResponse},
-    middleware::auth_middleware,
+    AppError, Audit, ROLE_TAG,
+    api_type::{ApiRequest, ApiResponse, DeleteParams},
+    call::Call,
+    middleware::{auth_middleware, require},
+};
+use crate::{
+    service::{audit::Entry, data_scope::DataScope, role, role_dept},
+    web_state::WebState,
 };
-use crate::{service::role, web_state::WebState};
 
 #[utoipa::path(
   post,
@@ -33,21 +41,18 @@ use crate::{service::role, web_state::WebState};
 )]
 pub async fn role_list<C>(
     State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
     Valid(Json(request)): Valid<Json<ApiRequest<ListRequest>>>,
-) -> Result<Json<ApiResponse<ListResponse>>, (StatusCode, String)>
+) -> Result<Json<ApiResponse<ListResponse>>, AppError>
 where
     C: ConnectionTrait,
 {
-    let roles = role::list(&state.db, request.params.page, request.params.page_size)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let mut call = Call::new(&state, &scope, &audit);
+    let data = ops::list(&mut call, request.params).await?;
+    call.finish();
 
-    let response = ApiResponse::new_success(
-        request.id,
-        ListResponse {
-            roles: roles.into_iter().map(|role| role.into()).collect(),
-        },
-    );
+    let response = ApiResponse::new_success(request.id, data);
     Ok(Json(response))
 }
 
@@ -63,19 +68,16 @@ where
 )]
 pub async fn role_create<C>(
     State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
     Valid(Json(request)): Valid<Json<ApiRequest<CreateRequest>>>,
-) -> Result<Json<ApiResponse<String>>, (StatusCode, String)>
+) -> Result<Json<ApiResponse<String>>, AppError>
 where
     C: ConnectionTrait,
 {
-    role::create(
-        &state.db,
-        &request.params.name,
-        request.params.data_scope,
-        request.params.status,
-    )
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::create(&mut call, request.params).await?;
+    call.finish();
 
     let response = ApiResponse::new_success(request.id, "success".to_string());
     Ok(Json(response))
@@ -84,7 +86,8 @@ where
 #[utoipa::path(
     get,
     path = "/role/delete/{id}",
-    responses((status = OK, body = ApiResponse<String>,content_type = "application/json", description = "delete user")),
+    params(DeleteParams),
+    responses((status = OK, body = ApiResponse<String>,content_type = "application/json", description = "soft delete role")),
     tag = ROLE_TAG,
     security(
         ("Bearer" = [])
@@ -92,14 +95,71 @@ where
 )]
 pub async fn role_delete<C>(
     State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
     Path(id): Path<i32>,
-) -> Result<Json<ApiResponse<String>>, (StatusCode, String)>
+    Query(params): Query<DeleteParams>,
+) -> Result<Json<ApiResponse<String>>, AppError>
 where
-    C: ConnectionTrait,
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::delete(&mut call, id, params.dependents).await?;
+    call.finish();
+
+    let response = ApiResponse::new_success(Value::Number(id.into()), "success".to_string());
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/role/restore/{id}",
+    responses((status = OK, body = ApiResponse<String>,content_type = "application/json", description = "restore soft deleted role")),
+    tag = ROLE_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn role_restore<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<String>>, AppError>
+where
+    C: ConnectionTrait + TransactionTrait,
 {
-    role::delete(&state.db, id)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::restore(&mut call, id).await?;
+    call.finish();
+
+    let response = ApiResponse::new_success(Value::Number(id.into()), "success".to_string());
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/role/purge/{id}",
+    params(DeleteParams),
+    responses((status = OK, body = ApiResponse<String>,content_type = "application/json", description = "permanently delete soft deleted role")),
+    tag = ROLE_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn role_purge<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Path(id): Path<i32>,
+    Query(params): Query<DeleteParams>,
+) -> Result<Json<ApiResponse<String>>, AppError>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::purge(&mut call, id, params.dependents).await?;
+    call.finish();
 
     let response = ApiResponse::new_success(Value::Number(id.into()), "success".to_string());
     Ok(Json(response))
@@ -117,20 +177,17 @@ where
 )]
 pub async fn role_update<C>(
     State(state): State<Arc<WebState<C>>>,
-    Json(request): Json<ApiRequest<UpdateRequest>>,
-) -> Result<Json<ApiResponse<String>>, (StatusCode, String)>
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Valid(Json(request)): Valid<Json<ApiRequest<UpdateRequest>>>,
+) -> Result<Json<ApiResponse<String>>, AppError>
 where
     C: ConnectionTrait,
 {
-    role::update(
-        &state.db,
-        request.params.id,
-        request.params.name,
-        request.params.data_scope,
-        request.params.status,
-    )
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let mut call = Call::new(&state, &scope, &audit);
+    let UpdateRequest { id, fields } = request.params;
+    ops::update(&mut call, id, fields).await?;
+    call.finish();
 
     let response = ApiResponse::new_success(request.id, "success".to_string());
     Ok(Json(response))
@@ -147,34 +204,170 @@ where
 )]
 pub async fn role_get<C>(
     State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<GetResponse>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    let data = ops::get(&mut call, id).await?;
+    call.finish();
+
+    let response = ApiResponse::new_success(Value::Number(id.into()), data);
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/role/menus/{id}",
+    responses((status = OK, body = ApiResponse<MenusResponse>,content_type = "application/json", description = "list menus of role")),
+    tag = ROLE_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn role_menus<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
     Path(id): Path<i32>,
-) -> Result<Json<ApiResponse<GetResponse>>, (StatusCode, String)>
+) -> Result<Json<ApiResponse<MenusResponse>>, AppError>
 where
     C: ConnectionTrait,
 {
-    let role = role::get(&state.db, id)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    if let Some(role) = role {
-        let response =
-            ApiResponse::new_success(Value::Number(id.into()), GetResponse { role: role.into() });
-        Ok(Json(response))
-    } else {
-        Err((StatusCode::NOT_FOUND, "Role not found".to_string()))
+    let mut call = Call::new(&state, &scope, &audit);
+    let data = ops::menus(&mut call, id).await?;
+    call.finish();
+
+    let response = ApiResponse::new_success(Value::Number(id.into()), data);
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/role/assign_menus",
+    request_body(content = ApiRequest<AssignMenusRequest>, content_type = "application/json"),
+    responses((status = OK, body = ApiResponse<MenusResponse>,content_type = "application/json", description = "replace menus of role")),
+    tag = ROLE_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn role_assign_menus<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Json(request): Json<ApiRequest<AssignMenusRequest>>,
+) -> Result<Json<ApiResponse<MenusResponse>>, AppError>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    let data = ops::assign_menus(&mut call, request.params).await?;
+    call.finish();
+
+    let response = ApiResponse::new_success(request.id, data);
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/role/depts/{id}",
+    responses((status = OK, body = ApiResponse<DeptsResponse>,content_type = "application/json", description = "list depts of role")),
+    tag = ROLE_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn role_depts<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<DeptsResponse>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    if role::get(&state.db, id).await?.is_none() {
+        return Err(AppError::not_found("Role not found"));
     }
+    let depts = role_dept::list(&state.db, id).await?;
+
+    let response = ApiResponse::new_success(
+        Value::Number(id.into()),
+        DeptsResponse {
+            depts: depts.into_iter().map(|dept| dept.into()).collect(),
+        },
+    );
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/role/assign_depts",
+    request_body(content = ApiRequest<AssignDeptsRequest>, content_type = "application/json"),
+    responses((status = OK, body = ApiResponse<DeptsResponse>,content_type = "application/json", description = "replace depts of role")),
+    tag = ROLE_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn role_assign_depts<C>(
+    State(state): State<Arc<WebState<C>>>,
+    audit: Audit,
+    Json(request): Json<ApiRequest<AssignDeptsRequest>>,
+) -> Result<Json<ApiResponse<DeptsResponse>>, AppError>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let role_id = request.params.role_id;
+    let before = role_dept::list(&state.db, role_id).await?;
+    let depts = role_dept::assign(&state.db, role_id, &request.params.dept_ids).await?;
+    state.permission_cache.invalidate_role(role_id);
+    audit
+        .record(
+            &state.db,
+            Entry::new("role.assign_depts")
+                .target("role", role_id)
+                .changed(
+                    &json!({ "dept_ids": before.iter().map(|dept| dept.id).collect::<Vec<_>>() }),
+                    &json!({ "dept_ids": depts.iter().map(|dept| dept.id).collect::<Vec<_>>() }),
+                ),
+        )
+        .await;
+
+    let response = ApiResponse::new_success(
+        request.id,
+        DeptsResponse {
+            depts: depts.into_iter().map(|dept| dept.into()).collect(),
+        },
+    );
+    Ok(Json(response))
 }
 
 pub fn router<C>(state: Arc<WebState<C>>) -> OpenApiRouter
 where
-    C: ConnectionTrait + Send + Sync + 'static,
+    C: ConnectionTrait + TransactionTrait + Send + Sync + 'static,
 {
     OpenApiRouter::new()
-        .routes(routes!(role_list))
-        .routes(routes!(role_create))
-        .routes(routes!(role_delete))
-        .routes(routes!(role_update))
-        .routes(routes!(role_get))
+        .routes(require(routes!(role_list), "role:list"))
+        .routes(require(routes!(role_create), "role:create"))
+        .routes(require(routes!(role_delete), "role:delete"))
+        .routes(require(routes!(role_restore), "role:delete"))
+        .routes(require(routes!(role_purge), "role:purge"))
+        .routes(require(routes!(role_update), "role:update"))
+        .routes(require(routes!(role_get), "role:get"))
+        .routes(require(routes!(role_menus), "role:menus"))
+        .routes(require(routes!(role_assign_menus), "role:assign_menus"))
+        .routes(require(routes!(role_depts), "role:depts"))
+        .routes(require(routes!(role_assign_depts), "role:assign_depts"))
+        .routes(require(routes!(rest::roles_list), "role:list"))
+        .routes(require(routes!(rest::roles_get), "role:get"))
+        .routes(require(routes!(rest::roles_create), "role:create"))
+        .routes(require(routes!(rest::roles_update), "role:update"))
+        .routes(require(routes!(rest::roles_delete), "role:delete"))
+        .routes(require(routes!(rest::roles_restore), "role:delete"))
+        .routes(require(routes!(rest::roles_purge), "role:purge"))
         .layer(middleware::from_fn_with_state(
             state.clone(),
             auth_middleware,
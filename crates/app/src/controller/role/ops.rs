@@ -0,0 +1,244 @@
+//! `/role/*`、`/roles` 和 `role.*` 共用的处理，接口只负责提取参数和组织响应
+
+use sea_orm::{ConnectionTrait, TransactionTrait};
+use serde_json::json;
+
+use super::types::{
+    AssignMenusRequest, CreateRequest, GetResponse, ListRequest, ListResponse, MenusResponse, Role,
+    UpdateFields,
+};
+use crate::{
+    controller::{
+        AppError,
+        call::{Call, Invalidation},
+    },
+    entity::RoleModel,
+    service::{audit::Entry, deletion::Dependents, permission, role, role_menu},
+};
+
+pub async fn list<C, D>(
+    call: &mut Call<'_, C, D>,
+    params: ListRequest,
+) -> Result<ListResponse, AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait,
+{
+    let page = role::list(call.db, &params).await?;
+    Ok(ListResponse {
+        roles: page.items.into_iter().map(|role| role.into()).collect(),
+        total: page.total,
+        page: page.page,
+        page_size: page.page_size,
+        next_cursor: page.next_cursor,
+    })
+}
+
+pub async fn get<C, D>(call: &mut Call<'_, C, D>, id: i32) -> Result<GetResponse, AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait,
+{
+    let role = find(call, id).await?;
+    Ok(GetResponse { role: role.into() })
+}
+
+pub async fn create<C, D>(
+    call: &mut Call<'_, C, D>,
+    params: CreateRequest,
+) -> Result<Role, AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait,
+{
+    let role = role::create(
+        call.db,
+        &params.name,
+        params.data_scope,
+        params.status,
+        params.is_superuser,
+    )
+    .await?;
+    let role = Role::from(role);
+    call.audit
+        .record(
+            call.db,
+            Entry::new("role.create")
+                .target("role", role.id)
+                .created(&role),
+        )
+        .await;
+    Ok(role)
+}
+
+pub async fn update<C, D>(
+    call: &mut Call<'_, C, D>,
+    id: i32,
+    params: UpdateFields,
+) -> Result<(), AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait,
+{
+    let before = find(call, id).await?;
+    role::update(
+        call.db,
+        id,
+        params.name,
+        params.data_scope,
+        params.status,
+        params.is_superuser,
+    )
+    .await?;
+    call.invalidations.push(Invalidation::Role(id));
+    if let Some(after) = role::get(call.db, id).await? {
+        call.audit
+            .record(
+                call.db,
+                Entry::new("role.update")
+                    .target("role", id)
+                    .changed(&Role::from(before), &Role::from(after)),
+            )
+            .await;
+    }
+    Ok(())
+}
+
+pub async fn delete<C, D>(
+    call: &mut Call<'_, C, D>,
+    id: i32,
+    dependents: Dependents,
+) -> Result<(), AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait + TransactionTrait,
+{
+    let role = find(call, id).await?;
+    role::delete(call.db, id, dependents).await?;
+    call.invalidations.push(Invalidation::Role(id));
+    call.audit
+        .record(
+            call.db,
+            Entry::new("role.delete")
+                .target("role", id)
+                .deleted(&Role::from(role)),
+        )
+        .await;
+    Ok(())
+}
+
+pub async fn restore<C, D>(call: &mut Call<'_, C, D>, id: i32) -> Result<(), AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait,
+{
+    if role::get_deleted(call.db, id).await?.is_none() {
+        return Err(AppError::not_found("Deleted role not found"));
+    }
+    let user_ids = role::restore(call.db, id).await?;
+    call.invalidations
+        .extend(user_ids.into_iter().map(Invalidation::User));
+    call.audit
+        .record(call.db, Entry::new("role.restore").target("role", id))
+        .await;
+    Ok(())
+}
+
+pub async fn purge<C, D>(
+    call: &mut Call<'_, C, D>,
+    id: i32,
+    dependents: Dependents,
+) -> Result<(), AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait + TransactionTrait,
+{
+    let role = role::get_deleted(call.db, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Deleted role not found"))?;
+    role::purge(call.db, id, dependents).await?;
+    call.invalidations.push(Invalidation::Role(id));
+    call.audit
+        .record(
+            call.db,
+            Entry::new("role.purge")
+                .target("role", id)
+                .deleted(&Role::from(role)),
+        )
+        .await;
+    Ok(())
+}
+
+pub async fn menus<C, D>(call: &mut Call<'_, C, D>, id: i32) -> Result<MenusResponse, AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait,
+{
+    find(call, id).await?;
+    let menus = role_menu::list(call.db, id).await?;
+    Ok(MenusResponse {
+        menus: menus.into_iter().map(|menu| menu.into()).collect(),
+    })
+}
+
+pub async fn assign_menus<C, D>(
+    call: &mut Call<'_, C, D>,
+    params: AssignMenusRequest,
+) -> Result<MenusResponse, AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait + TransactionTrait,
+{
+    let role_id = params.role_id;
+    find(call, role_id).await?;
+    let before = role_menu::list(call.db, role_id).await?;
+    // 否则拥有分配菜单权限的用户可以给自己的角色加上任意权限
+    if !call.is_superuser().await? {
+        let held = match call.audit.0.actor_id {
+            Some(actor_id) => permission::menu_ids(call.db, actor_id).await?,
+            None => Default::default(),
+        };
+        let mut changed = params
+            .menu_ids
+            .iter()
+            .filter(|id| !before.iter().any(|menu| menu.id == **id))
+            .chain(
+                before
+                    .iter()
+                    .map(|menu| &menu.id)
+                    .filter(|id| !params.menu_ids.contains(id)),
+            );
+        if changed.any(|id| !held.contains(id)) {
+            return Err(AppError::Forbidden(
+                "Only menus held by the caller can be assigned or removed".to_string(),
+            ));
+        }
+    }
+    let menus = role_menu::assign(call.db, role_id, &params.menu_ids).await?;
+    call.invalidations.push(Invalidation::Role(role_id));
+    call.audit
+        .record(
+            call.db,
+            Entry::new("role.assign_menus")
+                .target("role", role_id)
+                .changed(
+                    &json!({ "menu_ids": before.iter().map(|menu| menu.id).collect::<Vec<_>>() }),
+                    &json!({ "menu_ids": menus.iter().map(|menu| menu.id).collect::<Vec<_>>() }),
+                ),
+        )
+        .await;
+    Ok(MenusResponse {
+        menus: menus.into_iter().map(|menu| menu.into()).collect(),
+    })
+}
+
+/// 只查找未删除的角色
+async fn find<C, D>(call: &Call<'_, C, D>, id: i32) -> Result<RoleModel, AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait,
+{
+    role::get(call.db, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Role not found"))
+}
@@ -0,0 +1,233 @@
+use std::sync::Arc;
+
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use axum_valid::Valid;
+use sea_orm::{ConnectionTrait, TransactionTrait};
+use serde_json::Value;
+
+use super::{
+    ops,
+    types::{CreateRequest, GetResponse, ListResponse, UpdateFields},
+};
+use crate::{
+    controller::{
+        AppError, Audit, ROLE_TAG,
+        api_type::{ApiResponse, DeleteParams, ListParams},
+        call::Call,
+    },
+    service::data_scope::DataScope,
+    web_state::WebState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/roles",
+    params(ListParams),
+    responses((status = OK, body = ApiResponse<ListResponse>, content_type = "application/json", description = "list roles")),
+    tag = ROLE_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn roles_list<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Query(params): Query<ListParams>,
+) -> Result<Json<ApiResponse<ListResponse>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    let data = ops::list(&mut call, params.into_query()?).await?;
+    call.finish();
+
+    let response = ApiResponse::new_success(Value::Null, data);
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/roles/{id}",
+    responses(
+        (status = OK, body = ApiResponse<GetResponse>, content_type = "application/json", description = "get role"),
+        (status = NOT_FOUND, description = "role not found")
+    ),
+    tag = ROLE_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn roles_get<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Path(id): Path<i32>,
+) -> Result<Json<ApiResponse<GetResponse>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    let data = ops::get(&mut call, id).await?;
+    call.finish();
+
+    let response = ApiResponse::new_success(Value::Null, data);
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/roles",
+    request_body(content = CreateRequest, content_type = "application/json"),
+    responses((status = CREATED, body = ApiResponse<GetResponse>, content_type = "application/json", description = "create role")),
+    tag = ROLE_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn roles_create<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Valid(Json(params)): Valid<Json<CreateRequest>>,
+) -> Result<impl IntoResponse, AppError>
+where
+    C: ConnectionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    let role = ops::create(&mut call, params).await?;
+    call.finish();
+
+    let location = format!("/roles/{}", role.id);
+    let response = ApiResponse::new_success(Value::Null, GetResponse { role });
+    Ok((
+        StatusCode::CREATED,
+        [(header::LOCATION, location)],
+        Json(response),
+    ))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/roles/{id}",
+    request_body(content = UpdateFields, content_type = "application/json"),
+    responses(
+        (status = NO_CONTENT, description = "update role"),
+        (status = NOT_FOUND, description = "role not found")
+    ),
+    tag = ROLE_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn roles_update<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Path(id): Path<i32>,
+    Valid(Json(params)): Valid<Json<UpdateFields>>,
+) -> Result<StatusCode, AppError>
+where
+    C: ConnectionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::update(&mut call, id, params).await?;
+    call.finish();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/roles/{id}",
+    params(DeleteParams),
+    responses(
+        (status = NO_CONTENT, description = "soft delete role"),
+        (status = NOT_FOUND, description = "role not found"),
+        (status = UNPROCESSABLE_ENTITY, description = "role still has users, menus or depts and dependents is block")
+    ),
+    tag = ROLE_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn roles_delete<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Path(id): Path<i32>,
+    Query(params): Query<DeleteParams>,
+) -> Result<StatusCode, AppError>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::delete(&mut call, id, params.dependents).await?;
+    call.finish();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/roles/{id}/restore",
+    responses(
+        (status = NO_CONTENT, description = "restore soft deleted role"),
+        (status = NOT_FOUND, description = "deleted role not found")
+    ),
+    tag = ROLE_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn roles_restore<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, AppError>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::restore(&mut call, id).await?;
+    call.finish();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/roles/{id}/purge",
+    params(DeleteParams),
+    responses(
+        (status = NO_CONTENT, description = "permanently delete soft deleted role"),
+        (status = NOT_FOUND, description = "deleted role not found"),
+        (status = UNPROCESSABLE_ENTITY, description = "role still has users, menus or depts and dependents is block")
+    ),
+    tag = ROLE_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn roles_purge<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Path(id): Path<i32>,
+    Query(params): Query<DeleteParams>,
+) -> Result<StatusCode, AppError>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::purge(&mut call, id, params.dependents).await?;
+    call.finish();
+
+    Ok(StatusCode::NO_CONTENT)
+}
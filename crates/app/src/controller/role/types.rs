@@ -2,19 +2,13 @@ use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use validator::Validate;
 
-use crate::entity::RoleModel;
+use crate::{
+    entity::{DeptModel, MenuModel, RoleModel},
+    service::{query::ListQuery, role::RoleFilter},
+};
 
-#[derive(Deserialize, ToSchema, Validate)]
-pub struct ListRequest {
-    #[validate(range(min = 1, message = "page must be greater than 0"))]
-    pub page: u64,
-    #[validate(range(
-        min = 1,
-        max = 100,
-        message = "page_size must be greater than 0 and less than 100"
-    ))]
-    pub page_size: u64,
-}
+/// 关键字、过滤和排序字段见 [`role::list`](crate::service::role::list)
+pub type ListRequest = ListQuery<RoleFilter>;
 
 #[derive(Serialize, ToSchema)]
 pub struct Role {
@@ -22,6 +16,7 @@ pub struct Role {
     pub name: String,
     pub data_scope: i16,
     pub status: i16,
+    pub is_superuser: bool,
 }
 impl From<RoleModel> for Role {
     fn from(role: RoleModel) -> Self {
@@ -30,6 +25,7 @@ impl From<RoleModel> for Role {
             name: role.name,
             data_scope: role.data_scope,
             status: role.status,
+            is_superuser: role.is_superuser,
         }
     }
 }
@@ -37,32 +33,105 @@ impl From<RoleModel> for Role {
 #[derive(Serialize, ToSchema)]
 pub struct ListResponse {
     pub roles: Vec<Role>,
+    /// 满足条件的总数，按游标分页时不统计
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    pub page: u64,
+    pub page_size: u64,
+    /// 下一页的游标，只在按游标分页且还有数据时返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct CreateRequest {
     pub name: String,
+    /// 数据范围（0全部 1自定 2本部门 3仅本人）
     pub data_scope: i16,
     pub status: i16,
+    /// 超级管理员角色，拥有全部权限
+    #[serde(default)]
+    pub is_superuser: bool,
 }
 
-#[derive(Serialize, ToSchema)]
-pub struct CreateResponse {
-    pub role: Role,
-}
-
-#[derive(Deserialize, ToSchema)]
+/// `/role/update` 和 `role.update` 的参数
+#[derive(Deserialize, ToSchema, Validate)]
 pub struct UpdateRequest {
     pub id: i32,
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub fields: UpdateFields,
+}
+
+/// 要修改的字段，`PATCH /roles/{id}` 的请求体，id 取自路径
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct UpdateFields {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    /// 数据范围（0全部 1自定 2本部门 3仅本人）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data_scope: Option<i16>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub status: Option<i16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_superuser: Option<bool>,
 }
 
 #[derive(Serialize, ToSchema)]
 pub struct GetResponse {
     pub role: Role,
 }
+
+#[derive(Serialize, ToSchema)]
+pub struct Menu {
+    pub id: i32,
+    pub name: String,
+    pub path: String,
+    pub perms: String,
+}
+impl From<MenuModel> for Menu {
+    fn from(menu: MenuModel) -> Self {
+        Menu {
+            id: menu.id,
+            name: menu.name,
+            path: menu.path,
+            perms: menu.perms,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MenusResponse {
+    pub menus: Vec<Menu>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AssignMenusRequest {
+    pub role_id: i32,
+    pub menu_ids: Vec<i32>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Dept {
+    pub id: i64,
+    pub name: String,
+}
+impl From<DeptModel> for Dept {
+    fn from(dept: DeptModel) -> Self {
+        Dept {
+            id: dept.id,
+            name: dept.name,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeptsResponse {
+    pub depts: Vec<Dept>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AssignDeptsRequest {
+    pub role_id: i32,
+    pub dept_ids: Vec<i64>,
+}
@@ -1,20 +1,33 @@
 use std::sync::Arc;
 
-use sea_orm::ConnectionTrait;
+use axum::{extract::DefaultBodyLimit, middleware};
+use sea_orm::{ConnectionTrait, TransactionTrait};
 use utoipa_axum::router::OpenApiRouter;
 
 use crate::{
-    controller::{auth, menu, role, user},
+    controller::{
+        audit, auth,
+        error::{BODY_LIMIT, error_middleware},
+        menu,
+        middleware::request_id_middleware,
+        online, role, rpc, user,
+    },
     web_state::WebState,
 };
 
 pub fn router<C>(state: Arc<WebState<C>>) -> OpenApiRouter
 where
-    C: ConnectionTrait + Send + Sync + 'static,
+    C: ConnectionTrait + TransactionTrait + Send + Sync + 'static,
 {
     OpenApiRouter::new()
         .merge(auth::router(state.clone()))
         .merge(user::router(state.clone()))
         .merge(role::router(state.clone()))
         .merge(menu::router(state.clone()))
+        .merge(online::router(state.clone()))
+        .merge(rpc::router(state.clone()))
+        .merge(audit::router(state.clone()))
+        .layer(DefaultBodyLimit::max(BODY_LIMIT))
+        .layer(middleware::from_fn(error_middleware))
+        .layer(middleware::from_fn(request_id_middleware))
 }
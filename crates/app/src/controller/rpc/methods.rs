@@ -0,0 +1,187 @@
+use sea_orm::{ConnectionTrait, TransactionTrait};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use validator::{Validate, ValidateArgs};
+
+use super::types::{DeleteParams, IdParams, RpcError};
+use crate::controller::{
+    AppError,
+    call::Call,
+    menu::{ops as menu_ops, types as menu_types},
+    role::{ops as role_ops, types as role_types},
+    user::{ops as user_ops, types as user_types},
+};
+
+/// 可以通过 `/rpc` 调用的方法：方法名、需要的权限码和对应的 REST 路径，
+/// 权限与 REST 接口相同，开启 `prefix_match` 时按 REST 路径匹配菜单路径
+pub const METHODS: &[(&str, &str, &str)] = &[
+    ("user.list", "user:list", "/user/list"),
+    ("user.get", "user:get", "/user/get"),
+    ("user.create", "user:create", "/user/create"),
+    ("user.update", "user:update", "/user/update"),
+    ("user.delete", "user:delete", "/user/delete"),
+    ("user.restore", "user:delete", "/user/restore"),
+    ("user.purge", "user:purge", "/user/purge"),
+    ("user.roles", "user:roles", "/user/roles"),
+    (
+        "user.assign_roles",
+        "user:assign_roles",
+        "/user/assign_roles",
+    ),
+    ("role.list", "role:list", "/role/list"),
+    ("role.get", "role:get", "/role/get"),
+    ("role.create", "role:create", "/role/create"),
+    ("role.update", "role:update", "/role/update"),
+    ("role.delete", "role:delete", "/role/delete"),
+    ("role.restore", "role:delete", "/role/restore"),
+    ("role.purge", "role:purge", "/role/purge"),
+    ("role.menus", "role:menus", "/role/menus"),
+    (
+        "role.assign_menus",
+        "role:assign_menus",
+        "/role/assign_menus",
+    ),
+    ("menu.list", "menu:list", "/menu/list"),
+    ("menu.get", "menu:get", "/menu/get"),
+    ("menu.create", "menu:create", "/menu/create"),
+    ("menu.update", "menu:update", "/menu/update"),
+    ("menu.delete", "menu:delete", "/menu/delete"),
+    ("menu.restore", "menu:delete", "/menu/restore"),
+    ("menu.purge", "menu:purge", "/menu/purge"),
+];
+
+pub async fn dispatch<C, D>(
+    call: &mut Call<'_, C, D>,
+    method: &str,
+    params: Value,
+) -> Result<Value, RpcError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait + TransactionTrait,
+{
+    match method {
+        "user.list" => result(user_ops::list(call, validated(params)?).await?),
+        "user.get" => {
+            let params: IdParams<i64> = parse(params)?;
+            result(user_ops::get(call, params.id).await?)
+        }
+        "user.create" => {
+            let params: user_types::CreateRequest = parse(params)?;
+            params
+                .validate_with_args(&call.state.password_policy)
+                .map_err(RpcError::invalid_params)?;
+            user_ops::create(call, params).await?;
+            success()
+        }
+        "user.update" => {
+            let params: user_types::UpdateRequest = parse(params)?;
+            params
+                .validate_with_args(&call.state.password_policy)
+                .map_err(RpcError::invalid_params)?;
+            user_ops::update(call, params.id, params.fields).await?;
+            success()
+        }
+        "user.delete" => {
+            let params: DeleteParams<i64> = parse(params)?;
+            user_ops::delete(call, params.id, params.dependents).await?;
+            success()
+        }
+        "user.restore" => {
+            let params: IdParams<i64> = parse(params)?;
+            user_ops::restore(call, params.id).await?;
+            success()
+        }
+        "user.purge" => {
+            let params: DeleteParams<i64> = parse(params)?;
+            user_ops::purge(call, params.id, params.dependents).await?;
+            success()
+        }
+        "user.roles" => {
+            let params: IdParams<i64> = parse(params)?;
+            result(user_ops::roles(call, params.id).await?)
+        }
+        "user.assign_roles" => result(user_ops::assign_roles(call, parse(params)?).await?),
+        "role.list" => result(role_ops::list(call, validated(params)?).await?),
+        "role.get" => {
+            let params: IdParams<i32> = parse(params)?;
+            result(role_ops::get(call, params.id).await?)
+        }
+        "role.create" => {
+            role_ops::create(call, validated(params)?).await?;
+            success()
+        }
+        "role.update" => {
+            let params: role_types::UpdateRequest = validated(params)?;
+            role_ops::update(call, params.id, params.fields).await?;
+            success()
+        }
+        "role.delete" => {
+            let params: DeleteParams<i32> = parse(params)?;
+            role_ops::delete(call, params.id, params.dependents).await?;
+            success()
+        }
+        "role.restore" => {
+            let params: IdParams<i32> = parse(params)?;
+            role_ops::restore(call, params.id).await?;
+            success()
+        }
+        "role.purge" => {
+            let params: DeleteParams<i32> = parse(params)?;
+            role_ops::purge(call, params.id, params.dependents).await?;
+            success()
+        }
+        "role.menus" => {
+            let params: IdParams<i32> = parse(params)?;
+            result(role_ops::menus(call, params.id).await?)
+        }
+        "role.assign_menus" => result(role_ops::assign_menus(call, parse(params)?).await?),
+        "menu.list" => result(menu_ops::list(call, validated(params)?).await?),
+        "menu.get" => {
+            let params: IdParams<i32> = parse(params)?;
+            result(menu_ops::get(call, params.id).await?)
+        }
+        "menu.create" => {
+            menu_ops::create(call, validated(params)?).await?;
+            success()
+        }
+        "menu.update" => {
+            let params: menu_types::UpdateRequest = validated(params)?;
+            menu_ops::update(call, params.id, params.fields).await?;
+            success()
+        }
+        "menu.delete" => {
+            let params: DeleteParams<i32> = parse(params)?;
+            menu_ops::delete(call, params.id, params.dependents).await?;
+            success()
+        }
+        "menu.restore" => {
+            let params: IdParams<i32> = parse(params)?;
+            menu_ops::restore(call, params.id).await?;
+            success()
+        }
+        "menu.purge" => {
+            let params: DeleteParams<i32> = parse(params)?;
+            menu_ops::purge(call, params.id, params.dependents).await?;
+            success()
+        }
+        _ => Err(RpcError::method_not_found(method)),
+    }
+}
+
+fn parse<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(RpcError::invalid_params)
+}
+
+fn validated<T: DeserializeOwned + Validate>(params: Value) -> Result<T, RpcError> {
+    let params: T = parse(params)?;
+    params.validate().map_err(RpcError::invalid_params)?;
+    Ok(params)
+}
+
+fn result<T: Serialize>(data: T) -> Result<Value, RpcError> {
+    serde_json::to_value(data).map_err(|e| AppError::Internal(e.into()).into())
+}
+
+fn success() -> Result<Value, RpcError> {
+    result("success")
+}
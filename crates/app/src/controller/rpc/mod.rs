@@ -0,0 +1,218 @@
+mod methods;
+mod types;
+use methods::{METHODS, dispatch};
+use types::{RpcError, RpcOptions, RpcRequest, RpcResponse};
+
+use std::sync::Arc;
+
+use axum::{
+    Extension, Json,
+    body::Bytes,
+    extract::{Query, State},
+    http::StatusCode,
+    middleware,
+    response::{IntoResponse, Response},
+};
+use sea_orm::{ConnectionTrait, TransactionTrait};
+use serde_json::Value;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use super::{
+    AppError, Audit, RPC_TAG,
+    call::{Call, Invalidation},
+    middleware::auth_middleware,
+};
+use crate::{
+    service::{
+        data_scope::DataScope,
+        permission::{PermissionConfig, Permissions},
+    },
+    web_state::WebState,
+};
+
+/// 一次批量请求最多包含的调用数
+const MAX_BATCH: usize = 100;
+
+/// 当前用户的权限，逐个调用检查
+struct Access {
+    config: PermissionConfig,
+    permissions: Permissions,
+    scope: DataScope,
+    audit: Audit,
+}
+
+#[utoipa::path(
+    post,
+    path = "/rpc",
+    params(RpcOptions),
+    request_body(content = Vec<RpcRequest>, content_type = "application/json", description = "a request object or a batch of them"),
+    responses(
+        (status = OK, body = Vec<RpcResponse>, content_type = "application/json", description = "a response object or a batch of them"),
+        (status = NO_CONTENT, description = "only notifications")
+    ),
+    tag = RPC_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn rpc<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(config): Extension<PermissionConfig>,
+    Extension(permissions): Extension<Permissions>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Query(options): Query<RpcOptions>,
+    body: Bytes,
+) -> Result<Response, AppError>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let (requests, batch) = match serde_json::from_slice::<Value>(&body) {
+        Err(_) => {
+            return Ok(single(RpcResponse::error(
+                Value::Null,
+                RpcError::parse_error(),
+            )));
+        }
+        Ok(Value::Array(requests)) if requests.is_empty() => {
+            return Ok(single(RpcResponse::error(
+                Value::Null,
+                RpcError::invalid_request("Empty batch"),
+            )));
+        }
+        Ok(Value::Array(requests)) if requests.len() > MAX_BATCH => {
+            return Ok(single(RpcResponse::error(
+                Value::Null,
+                RpcError::invalid_request(format!("Batch exceeds {} requests", MAX_BATCH)),
+            )));
+        }
+        Ok(Value::Array(requests)) => (requests, true),
+        Ok(request) => (vec![request], false),
+    };
+    let access = Access {
+        config,
+        permissions,
+        scope,
+        audit,
+    };
+
+    let responses = if options.transaction {
+        let txn = state.db.begin().await?;
+        let (responses, invalidations, failed) =
+            execute(&state, &txn, &access, requests, true).await;
+        if failed {
+            txn.rollback().await?;
+        } else {
+            txn.commit().await?;
+            invalidations
+                .into_iter()
+                .for_each(|invalidation| invalidation.apply(&state.permission_cache));
+        }
+        responses
+    } else {
+        let (responses, invalidations, _) =
+            execute(&state, &state.db, &access, requests, false).await;
+        invalidations
+            .into_iter()
+            .for_each(|invalidation| invalidation.apply(&state.permission_cache));
+        responses
+    };
+
+    // 通知不返回响应，全部是通知时不返回任何内容
+    let responses: Vec<RpcResponse> = responses.into_iter().flatten().collect();
+    Ok(match responses.len() {
+        0 => StatusCode::NO_CONTENT.into_response(),
+        _ if batch => Json(responses).into_response(),
+        _ => single(responses.into_iter().next().expect("one response")),
+    })
+}
+
+fn single(response: RpcResponse) -> Response {
+    Json(response).into_response()
+}
+
+/// 按顺序执行每个调用，返回各调用的响应（通知为 `None`）、待失效的缓存和是否有调用失败；
+/// `atomic` 为真时遇到失败即停止，已成功和未执行的调用都返回回滚错误
+async fn execute<C, D>(
+    state: &WebState<C>,
+    db: &D,
+    access: &Access,
+    requests: Vec<Value>,
+    atomic: bool,
+) -> (Vec<Option<RpcResponse>>, Vec<Invalidation>, bool)
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait + TransactionTrait,
+{
+    let mut call = Call {
+        state,
+        db,
+        scope: &access.scope,
+        audit: &access.audit,
+        invalidations: Vec::new(),
+    };
+    let mut outcomes = Vec::with_capacity(requests.len());
+    let mut failed = false;
+
+    for request in requests {
+        let id = request.get("id").cloned();
+        if atomic && failed {
+            outcomes.push((id, Err(RpcError::rolled_back())));
+            continue;
+        }
+
+        let (id, result) = match serde_json::from_value::<RpcRequest>(request) {
+            Ok(request) if request.jsonrpc == types::JSONRPC_VERSION => {
+                let result = match authorize(access, &request.method) {
+                    Ok(()) => dispatch(&mut call, &request.method, request.params).await,
+                    Err(e) => Err(e),
+                };
+                (request.id, result)
+            }
+            // 无法识别的请求即使没有 `id` 也返回错误
+            _ => (
+                Some(id.unwrap_or(Value::Null)),
+                Err(RpcError::invalid_request("Invalid request")),
+            ),
+        };
+        failed |= result.is_err();
+        outcomes.push((id, result));
+    }
+
+    let responses = outcomes
+        .into_iter()
+        .map(|(id, result)| {
+            let id = id?;
+            Some(match result {
+                Ok(_) if atomic && failed => RpcResponse::error(id, RpcError::rolled_back()),
+                Ok(result) => RpcResponse::success(id, result),
+                Err(e) => RpcResponse::error(id, e),
+            })
+        })
+        .collect();
+    (responses, call.invalidations, failed)
+}
+
+/// 与 REST 接口使用相同的权限码
+fn authorize(access: &Access, method: &str) -> Result<(), RpcError> {
+    let Some((_, code, path)) = METHODS.iter().find(|(name, _, _)| *name == method) else {
+        return Err(RpcError::method_not_found(method));
+    };
+    if !access.permissions.allows(&access.config, code, path) {
+        return Err(AppError::Forbidden("No permission".to_string()).into());
+    }
+    Ok(())
+}
+
+pub fn router<C>(state: Arc<WebState<C>>) -> OpenApiRouter
+where
+    C: ConnectionTrait + TransactionTrait + Send + Sync + 'static,
+{
+    OpenApiRouter::new()
+        .routes(routes!(rpc))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ))
+        .with_state(state)
+}
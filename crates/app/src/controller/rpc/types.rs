@@ -0,0 +1,147 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{controller::AppError, service::deletion::Dependents};
+
+pub const JSONRPC_VERSION: &str = "2.0";
+
+// JSON-RPC 2.0 规定的错误码，业务错误沿用 `api_type` 中的错误码
+
+/// 请求体不是合法的 JSON
+pub const PARSE_ERROR_CODE: i32 = -32700;
+/// 不是合法的 JSON-RPC 请求对象，或批量请求为空、超出上限
+pub const INVALID_REQUEST_CODE: i32 = -32600;
+pub const METHOD_NOT_FOUND_CODE: i32 = -32601;
+/// `params` 无法解析或校验失败
+pub const INVALID_PARAMS_CODE: i32 = -32602;
+/// 同一事务中的其他调用失败，本调用的修改已回滚或未执行
+pub const ROLLED_BACK_CODE: i32 = -32000;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RpcOptions {
+    /// 整批调用在同一个事务中执行，任一调用失败时全部回滚
+    #[serde(default)]
+    pub transaction: bool,
+}
+
+/// 与 `ApiRequest` 相同的 `id` 和 `params`，另加 `jsonrpc` 和 `method`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RpcRequest {
+    /// 固定为 `2.0`
+    pub jsonrpc: String,
+    /// 方法名，例如 `user.list`、`role.update`
+    pub method: String,
+    /// 与对应接口 `ApiRequest` 中的 `params` 相同
+    #[serde(default)]
+    pub params: Value,
+    /// 省略时为通知，执行后不返回响应
+    #[serde(default, deserialize_with = "present")]
+    pub id: Option<Value>,
+}
+
+/// 区分省略的 `id` 和值为 `null` 的 `id`
+fn present<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Value>, D::Error> {
+    Value::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RpcResponse {
+    pub jsonrpc: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+    pub id: Value,
+}
+
+impl RpcResponse {
+    pub fn success(id: Value, result: Value) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            result: Some(result),
+            error: None,
+            id,
+        }
+    }
+
+    pub fn error(id: Value, error: RpcError) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            result: None,
+            error: Some(error),
+            id,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RpcError {
+    pub code: i32,
+    pub message: String,
+}
+
+impl RpcError {
+    pub fn parse_error() -> Self {
+        Self {
+            code: PARSE_ERROR_CODE,
+            message: "Parse error".to_string(),
+        }
+    }
+
+    pub fn invalid_request(message: impl Into<String>) -> Self {
+        Self {
+            code: INVALID_REQUEST_CODE,
+            message: message.into(),
+        }
+    }
+
+    pub fn method_not_found(method: &str) -> Self {
+        Self {
+            code: METHOD_NOT_FOUND_CODE,
+            message: format!("Method `{}` not found", method),
+        }
+    }
+
+    pub fn invalid_params(message: impl ToString) -> Self {
+        Self {
+            code: INVALID_PARAMS_CODE,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn rolled_back() -> Self {
+        Self {
+            code: ROLLED_BACK_CODE,
+            message: "Transaction rolled back".to_string(),
+        }
+    }
+}
+
+impl From<AppError> for RpcError {
+    fn from(e: AppError) -> Self {
+        let (code, message) = e.report();
+        Self { code, message }
+    }
+}
+
+impl From<anyhow::Error> for RpcError {
+    fn from(e: anyhow::Error) -> Self {
+        AppError::from(e).into()
+    }
+}
+
+/// `user.get`、`user.delete` 等方法的参数，对应 REST 接口路径中的 `{id}`
+#[derive(Debug, Deserialize)]
+pub struct IdParams<T> {
+    pub id: T,
+}
+
+/// `user.delete`、`user.purge` 等方法的参数，`dependents` 对应 REST 接口的查询参数
+#[derive(Debug, Deserialize)]
+pub struct DeleteParams<T> {
+    pub id: T,
+    #[serde(default)]
+    pub dependents: Dependents,
+}
@@ -1,25 +1,30 @@
-mod types;
-use types::{CreateRequest, GetResponse, ListRequest, ListResponse, UpdateRequest};
+pub(super) mod ops;
+mod rest;
+pub(super) mod types;
+use types::{
+    AssignRolesRequest, CreateRequest, GetResponse, ListRequest, ListResponse, RolesResponse,
+    UpdateRequest,
+};
 
 use std::sync::Arc;
 
 use axum::{
-    Json,
-    extract::{Path, State},
-    http::StatusCode,
+    Extension, Json,
+    extract::{Path, Query, State},
     middleware,
 };
-use axum_valid::Valid;
-use sea_orm::ConnectionTrait;
+use axum_valid::{Valid, ValidEx};
+use sea_orm::{ConnectionTrait, TransactionTrait};
 use serde_json::Value;
 use utoipa_axum::{router::OpenApiRouter, routes};
 
 use super::{
-    USER_TAG,
-    api_type::{ApiRequest, ApiResponse},
-    middleware::auth_middleware,
+    AppError, Audit, USER_TAG,
+    api_type::{ApiRequest, ApiResponse, DeleteParams},
+    call::Call,
+    middleware::{auth_middleware, require},
 };
-use crate::{service::user, web_state::WebState};
+use crate::{service::data_scope::DataScope, web_state::WebState};
 
 #[utoipa::path(
   post,
@@ -33,21 +38,18 @@ use crate::{service::user, web_state::WebState};
 )]
 pub async fn user_list<C>(
     State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
     Valid(Json(request)): Valid<Json<ApiRequest<ListRequest>>>,
-) -> Result<Json<ApiResponse<ListResponse>>, (StatusCode, String)>
+) -> Result<Json<ApiResponse<ListResponse>>, AppError>
 where
     C: ConnectionTrait,
 {
-    let users = user::list(&state.db, request.params.page, request.params.page_size)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    let response = ApiResponse::new_success(
-        request.id,
-        ListResponse {
-            users: users.into_iter().map(|user| user.into()).collect(),
-        },
-    );
+    let mut call = Call::new(&state, &scope, &audit);
+    let data = ops::list(&mut call, request.params).await?;
+    call.finish();
+
+    let response = ApiResponse::new_success(request.id, data);
     Ok(Json(response))
 }
 
@@ -63,24 +65,16 @@ where
 )]
 pub async fn user_create<C>(
     State(state): State<Arc<WebState<C>>>,
-    Valid(Json(request)): Valid<Json<ApiRequest<CreateRequest>>>,
-) -> Result<Json<ApiResponse<String>>, (StatusCode, String)>
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    ValidEx(Json(request)): ValidEx<Json<ApiRequest<CreateRequest>>>,
+) -> Result<Json<ApiResponse<String>>, AppError>
 where
     C: ConnectionTrait,
 {
-    if !check_user_exists(&state.db, None, Some(&request.params.username)).await? {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            "Username already exists".to_string(),
-        ));
-    }
-    user::create(
-        &state.db,
-        &request.params.username,
-        &request.params.password,
-    )
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::create(&mut call, request.params).await?;
+    call.finish();
 
     let response = ApiResponse::new_success_without_data(request.id);
     Ok(Json(response))
@@ -89,7 +83,8 @@ where
 #[utoipa::path(
     get,
     path = "/user/delete/{id}",
-    responses((status = OK, body = ApiResponse<String>,content_type = "application/json", description = "delete user")),
+    params(DeleteParams),
+    responses((status = OK, body = ApiResponse<String>,content_type = "application/json", description = "soft delete user")),
     tag = USER_TAG,
     security(
         ("Bearer" = [])
@@ -97,17 +92,71 @@ where
 )]
 pub async fn user_delete<C>(
     State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Path(id): Path<i64>,
+    Query(params): Query<DeleteParams>,
+) -> Result<Json<ApiResponse<String>>, AppError>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::delete(&mut call, id, params.dependents).await?;
+    call.finish();
+
+    let response = ApiResponse::new_success_without_data(Value::Number(id.into()));
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/user/restore/{id}",
+    responses((status = OK, body = ApiResponse<String>,content_type = "application/json", description = "restore soft deleted user")),
+    tag = USER_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn user_restore<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
     Path(id): Path<i64>,
-) -> Result<Json<ApiResponse<String>>, (StatusCode, String)>
+) -> Result<Json<ApiResponse<String>>, AppError>
 where
     C: ConnectionTrait,
 {
-    if !check_user_exists(&state.db, Some(id), None).await? {
-        return Err((StatusCode::BAD_REQUEST, "User not found".to_string()));
-    }
-    user::delete(&state.db, id)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::restore(&mut call, id).await?;
+    call.finish();
+
+    let response = ApiResponse::new_success_without_data(Value::Number(id.into()));
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/user/purge/{id}",
+    params(DeleteParams),
+    responses((status = OK, body = ApiResponse<String>,content_type = "application/json", description = "permanently delete soft deleted user")),
+    tag = USER_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn user_purge<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Path(id): Path<i64>,
+    Query(params): Query<DeleteParams>,
+) -> Result<Json<ApiResponse<String>>, AppError>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::purge(&mut call, id, params.dependents).await?;
+    call.finish();
 
     let response = ApiResponse::new_success_without_data(Value::Number(id.into()));
     Ok(Json(response))
@@ -125,22 +174,17 @@ where
 )]
 pub async fn user_update<C>(
     State(state): State<Arc<WebState<C>>>,
-    Json(request): Json<ApiRequest<UpdateRequest>>,
-) -> Result<Json<ApiResponse<String>>, (StatusCode, String)>
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    ValidEx(Json(request)): ValidEx<Json<ApiRequest<UpdateRequest>>>,
+) -> Result<Json<ApiResponse<String>>, AppError>
 where
-    C: ConnectionTrait,
+    C: ConnectionTrait + TransactionTrait,
 {
-    if !check_user_exists(&state.db, Some(request.params.id), None).await? {
-        return Err((StatusCode::BAD_REQUEST, "User not found".to_string()));
-    }
-    user::update(
-        &state.db,
-        request.params.id,
-        request.params.username,
-        request.params.password,
-    )
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let UpdateRequest { id, fields } = request.params;
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::update(&mut call, id, fields).await?;
+    call.finish();
 
     let response = ApiResponse::new_success_without_data(request.id);
     Ok(Json(response))
@@ -157,56 +201,95 @@ where
 )]
 pub async fn user_get<C>(
     State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
     Path(id): Path<i64>,
-) -> Result<Json<ApiResponse<GetResponse>>, (StatusCode, String)>
+) -> Result<Json<ApiResponse<GetResponse>>, AppError>
 where
     C: ConnectionTrait,
 {
-    let user = user::get(&state.db, id)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    if let Some(user) = user {
-        let response =
-            ApiResponse::new_success(Value::Number(id.into()), GetResponse { user: user.into() });
-        Ok(Json(response))
-    } else {
-        Err((StatusCode::NOT_FOUND, "User not found".to_string()))
-    }
+    let mut call = Call::new(&state, &scope, &audit);
+    let data = ops::get(&mut call, id).await?;
+    call.finish();
+
+    let response = ApiResponse::new_success(Value::Number(id.into()), data);
+    Ok(Json(response))
 }
 
-async fn check_user_exists<C: ConnectionTrait>(
-    db: &C,
-    id: Option<i64>,
-    username: Option<&str>,
-) -> Result<bool, (StatusCode, String)> {
-    if let Some(id) = id {
-        let user = user::get(db, id)
-            .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-        Ok(user.is_some())
-    } else if let Some(username) = username {
-        let user = user::get_by_username(db, username)
-            .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-        Ok(user.is_some())
-    } else {
-        Ok(false)
-    }
+#[utoipa::path(
+    get,
+    path = "/user/roles/{id}",
+    responses((status = OK, body = ApiResponse<RolesResponse>,content_type = "application/json", description = "list roles of user")),
+    tag = USER_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn user_roles<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Path(id): Path<i64>,
+) -> Result<Json<ApiResponse<RolesResponse>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    let data = ops::roles(&mut call, id).await?;
+    call.finish();
+
+    let response = ApiResponse::new_success(Value::Number(id.into()), data);
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/user/assign_roles",
+    request_body(content = ApiRequest<AssignRolesRequest>, content_type = "application/json"),
+    responses((status = OK, body = ApiResponse<RolesResponse>,content_type = "application/json", description = "replace roles of user")),
+    tag = USER_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn user_assign_roles<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Json(request): Json<ApiRequest<AssignRolesRequest>>,
+) -> Result<Json<ApiResponse<RolesResponse>>, AppError>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    let data = ops::assign_roles(&mut call, request.params).await?;
+    call.finish();
+
+    let response = ApiResponse::new_success(request.id, data);
+    Ok(Json(response))
 }
 
 pub fn router<C>(state: Arc<WebState<C>>) -> OpenApiRouter
 where
-    C: ConnectionTrait + Send + Sync + 'static,
+    C: ConnectionTrait + TransactionTrait + Send + Sync + 'static,
 {
     OpenApiRouter::new()
-        .routes(routes!(user_list))
-        .routes(routes!(user_create))
-        .routes(routes!(user_delete))
-        .routes(routes!(user_update))
-        .routes(routes!(user_get))
+        .routes(require(routes!(user_list), "user:list"))
+        .routes(require(routes!(user_create), "user:create"))
+        .routes(require(routes!(user_delete), "user:delete"))
+        .routes(require(routes!(user_restore), "user:delete"))
+        .routes(require(routes!(user_purge), "user:purge"))
+        .routes(require(routes!(user_update), "user:update"))
+        .routes(require(routes!(user_get), "user:get"))
+        .routes(require(routes!(user_roles), "user:roles"))
+        .routes(require(routes!(user_assign_roles), "user:assign_roles"))
+        .routes(require(routes!(rest::users_list), "user:list"))
+        .routes(require(routes!(rest::users_get), "user:get"))
+        .routes(require(routes!(rest::users_create), "user:create"))
+        .routes(require(routes!(rest::users_update), "user:update"))
+        .routes(require(routes!(rest::users_delete), "user:delete"))
+        .routes(require(routes!(rest::users_restore), "user:delete"))
+        .routes(require(routes!(rest::users_purge), "user:purge"))
         .layer(middleware::from_fn_with_state(
             state.clone(),
             auth_middleware,
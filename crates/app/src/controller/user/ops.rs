@@ -0,0 +1,292 @@
+//! `/user/*`、`/users` 和 `user.*` 共用的处理，接口只负责提取参数和组织响应
+
+use sea_orm::{ConnectionTrait, TransactionTrait};
+use serde_json::Value;
+
+use super::types::{
+    AssignRolesRequest, CreateRequest, GetResponse, ListRequest, ListResponse, RolesResponse,
+    UpdateFields, User,
+};
+use crate::{
+    controller::{
+        AppError,
+        call::{Call, Invalidation},
+    },
+    entity::{RoleModel, UserModel},
+    service::{
+        audit::Entry,
+        data_scope::DataScope,
+        deletion::Dependents,
+        dept,
+        password_policy::not_username,
+        permission, role,
+        user::{self, Profile},
+        user_role,
+    },
+};
+
+pub async fn list<C, D>(
+    call: &mut Call<'_, C, D>,
+    params: ListRequest,
+) -> Result<ListResponse, AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait,
+{
+    let page = user::list(call.db, call.scope, &params).await?;
+    Ok(ListResponse {
+        users: page.items.into_iter().map(|user| user.into()).collect(),
+        total: page.total,
+        page: page.page,
+        page_size: page.page_size,
+        next_cursor: page.next_cursor,
+    })
+}
+
+pub async fn get<C, D>(call: &mut Call<'_, C, D>, id: i64) -> Result<GetResponse, AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait,
+{
+    let user = find(call, id).await?;
+    Ok(GetResponse { user: user.into() })
+}
+
+pub async fn create<C, D>(
+    call: &mut Call<'_, C, D>,
+    params: CreateRequest,
+) -> Result<User, AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait,
+{
+    let user = user::create_with_profile(
+        call.db,
+        call.state.hasher.as_ref(),
+        &params.username,
+        &params.password,
+        Profile {
+            email: params.email,
+            phone: params.phone,
+            nickname: params.nickname,
+            avatar: params.avatar,
+        },
+    )
+    .await?;
+    let user = User::from(user);
+    call.audit
+        .record(
+            call.db,
+            Entry::new("user.create")
+                .target("user", user.id)
+                .created(&user),
+        )
+        .await;
+    Ok(user)
+}
+
+pub async fn update<C, D>(
+    call: &mut Call<'_, C, D>,
+    id: i64,
+    params: UpdateFields,
+) -> Result<(), AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait + TransactionTrait,
+{
+    let user = find(call, id).await?;
+    if let Some(password) = &params.password {
+        let username = params.username.as_ref().unwrap_or(&user.name);
+        not_username(username, password).map_err(|e| AppError::bad_request(e.to_string()))?;
+    }
+    if let Some(dept_id) = params.dept_id {
+        if dept::get(call.db, dept_id).await?.is_none() {
+            return Err(AppError::not_found("Dept not found"));
+        }
+        // 不能把用户移到自己数据范围以外的部门
+        if !call.scope.covers_dept(dept_id) {
+            return Err(AppError::Forbidden("Dept out of data scope".to_string()));
+        }
+    }
+
+    // 任何一项冲突或被拒绝时都不保留其他修改
+    let password_changed = params.password.is_some();
+    let txn = call.db.begin().await?;
+    if params.dept_id.is_some() {
+        user::set_dept(&txn, user.id, params.dept_id).await?;
+    }
+    user::set_profile(
+        &txn,
+        user.id,
+        Profile {
+            email: params.email,
+            phone: params.phone,
+            nickname: params.nickname,
+            avatar: params.avatar,
+        },
+    )
+    .await?;
+    if let Some(password) = &params.password {
+        user::change_password(
+            &txn,
+            call.state.hasher.as_ref(),
+            &user,
+            password,
+            call.state.password_policy.history,
+        )
+        .await?;
+    }
+    user::update(&txn, user.id, params.username, params.status).await?;
+    txn.commit().await?;
+    call.invalidations.push(Invalidation::User(id));
+
+    // 修改后可能已经不在 `scope` 范围内
+    if let Some(after) = user::get(call.db, &DataScope::all(), id).await? {
+        let mut entry = Entry::new("user.update")
+            .target("user", id)
+            .changed(&User::from(user), &User::from(after));
+        if password_changed && let Some(Value::Object(after)) = &mut entry.after {
+            after.insert("password_changed".to_string(), Value::Bool(true));
+        }
+        call.audit.record(call.db, entry).await;
+    }
+    Ok(())
+}
+
+pub async fn delete<C, D>(
+    call: &mut Call<'_, C, D>,
+    id: i64,
+    dependents: Dependents,
+) -> Result<(), AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait + TransactionTrait,
+{
+    let user = find(call, id).await?;
+    user::delete(call.db, id, dependents).await?;
+    call.invalidations.push(Invalidation::User(id));
+    call.audit
+        .record(
+            call.db,
+            Entry::new("user.delete")
+                .target("user", id)
+                .deleted(&User::from(user)),
+        )
+        .await;
+    Ok(())
+}
+
+pub async fn restore<C, D>(call: &mut Call<'_, C, D>, id: i64) -> Result<(), AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait,
+{
+    if user::get_deleted(call.db, call.scope, id).await?.is_none() {
+        return Err(AppError::not_found("Deleted user not found"));
+    }
+    user::restore(call.db, id).await?;
+    call.invalidations.push(Invalidation::User(id));
+    call.audit
+        .record(call.db, Entry::new("user.restore").target("user", id))
+        .await;
+    Ok(())
+}
+
+pub async fn purge<C, D>(
+    call: &mut Call<'_, C, D>,
+    id: i64,
+    dependents: Dependents,
+) -> Result<(), AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait + TransactionTrait,
+{
+    let user = user::get_deleted(call.db, call.scope, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Deleted user not found"))?;
+    user::purge(call.db, id, dependents).await?;
+    call.invalidations.push(Invalidation::User(id));
+    call.audit
+        .record(
+            call.db,
+            Entry::new("user.purge")
+                .target("user", id)
+                .deleted(&User::from(user)),
+        )
+        .await;
+    Ok(())
+}
+
+pub async fn roles<C, D>(call: &mut Call<'_, C, D>, id: i64) -> Result<RolesResponse, AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait,
+{
+    find(call, id).await?;
+    let roles = user_role::list(call.db, id).await?;
+    Ok(RolesResponse {
+        roles: roles.into_iter().map(|role| role.into()).collect(),
+    })
+}
+
+pub async fn assign_roles<C, D>(
+    call: &mut Call<'_, C, D>,
+    params: AssignRolesRequest,
+) -> Result<RolesResponse, AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait + TransactionTrait,
+{
+    let user_id = params.user_id;
+    find(call, user_id).await?;
+    let before = user_role::list(call.db, user_id).await?;
+    // 否则拥有分配角色权限的用户可以给自己授予超级管理员角色
+    let rule = &call.state.permission.superuser;
+    let mut superuser = before
+        .iter()
+        .filter(|role| !params.role_ids.contains(&role.id))
+        .any(|role| permission::grants_superuser(rule, role));
+    for id in &params.role_ids {
+        if !before.iter().any(|role| role.id == *id)
+            && let Some(role) = role::get(call.db, *id).await?
+        {
+            superuser |= permission::grants_superuser(rule, &role);
+        }
+    }
+    if superuser && !call.is_superuser().await? {
+        return Err(AppError::Forbidden(
+            "Only superusers can assign or remove superuser roles".to_string(),
+        ));
+    }
+    let roles = user_role::assign(call.db, user_id, &params.role_ids).await?;
+    call.invalidations.push(Invalidation::User(user_id));
+    call.audit
+        .record(
+            call.db,
+            Entry::new("user.assign_roles")
+                .target("user", user_id)
+                .changed(&role_ids(&before), &role_ids(&roles)),
+        )
+        .await;
+    Ok(RolesResponse {
+        roles: roles.into_iter().map(|role| role.into()).collect(),
+    })
+}
+
+/// 只查找 `scope` 范围内未删除的用户
+async fn find<C, D>(call: &Call<'_, C, D>, id: i64) -> Result<UserModel, AppError>
+where
+    C: ConnectionTrait,
+    D: ConnectionTrait,
+{
+    user::get(call.db, call.scope, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("User not found"))
+}
+
+/// 分配角色前后的角色 id，用于审计日志
+fn role_ids(roles: &[RoleModel]) -> Value {
+    serde_json::json!({
+        "role_ids": roles.iter().map(|role| role.id).collect::<Vec<_>>()
+    })
+}
@@ -0,0 +1,237 @@
+use std::sync::Arc;
+
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use axum_valid::ValidEx;
+use sea_orm::{ConnectionTrait, TransactionTrait};
+use serde_json::Value;
+
+use super::{
+    ops,
+    types::{CreateRequest, GetResponse, ListResponse, UpdateFields},
+};
+use crate::{
+    controller::{
+        AppError, Audit, USER_TAG,
+        api_type::{ApiResponse, DeleteParams, ListParams},
+        call::Call,
+    },
+    service::data_scope::DataScope,
+    web_state::WebState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/users",
+    params(ListParams),
+    responses((status = OK, body = ApiResponse<ListResponse>, content_type = "application/json", description = "list users")),
+    tag = USER_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn users_list<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Query(params): Query<ListParams>,
+) -> Result<Json<ApiResponse<ListResponse>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    let data = ops::list(&mut call, params.into_query()?).await?;
+    call.finish();
+
+    let response = ApiResponse::new_success(Value::Null, data);
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/{id}",
+    responses(
+        (status = OK, body = ApiResponse<GetResponse>, content_type = "application/json", description = "get user"),
+        (status = NOT_FOUND, description = "user not found")
+    ),
+    tag = USER_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn users_get<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Path(id): Path<i64>,
+) -> Result<Json<ApiResponse<GetResponse>>, AppError>
+where
+    C: ConnectionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    let data = ops::get(&mut call, id).await?;
+    call.finish();
+
+    let response = ApiResponse::new_success(Value::Null, data);
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/users",
+    request_body(content = CreateRequest, content_type = "application/json"),
+    responses(
+        (status = CREATED, body = ApiResponse<GetResponse>, content_type = "application/json", description = "create user"),
+        (status = CONFLICT, description = "username or email already exists")
+    ),
+    tag = USER_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn users_create<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    ValidEx(Json(params)): ValidEx<Json<CreateRequest>>,
+) -> Result<impl IntoResponse, AppError>
+where
+    C: ConnectionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    let user = ops::create(&mut call, params).await?;
+    call.finish();
+
+    let location = format!("/users/{}", user.id);
+    let response = ApiResponse::new_success(Value::Null, GetResponse { user });
+    Ok((
+        StatusCode::CREATED,
+        [(header::LOCATION, location)],
+        Json(response),
+    ))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/users/{id}",
+    request_body(content = UpdateFields, content_type = "application/json"),
+    responses(
+        (status = NO_CONTENT, description = "update user"),
+        (status = NOT_FOUND, description = "user not found"),
+        (status = CONFLICT, description = "username or email already exists")
+    ),
+    tag = USER_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn users_update<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Path(id): Path<i64>,
+    ValidEx(Json(params)): ValidEx<Json<UpdateFields>>,
+) -> Result<StatusCode, AppError>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::update(&mut call, id, params).await?;
+    call.finish();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/users/{id}",
+    params(DeleteParams),
+    responses(
+        (status = NO_CONTENT, description = "soft delete user"),
+        (status = NOT_FOUND, description = "user not found"),
+        (status = UNPROCESSABLE_ENTITY, description = "user still has roles and dependents is block")
+    ),
+    tag = USER_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn users_delete<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Path(id): Path<i64>,
+    Query(params): Query<DeleteParams>,
+) -> Result<StatusCode, AppError>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::delete(&mut call, id, params.dependents).await?;
+    call.finish();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/users/{id}/restore",
+    responses(
+        (status = NO_CONTENT, description = "restore soft deleted user"),
+        (status = NOT_FOUND, description = "deleted user not found")
+    ),
+    tag = USER_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn users_restore<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, AppError>
+where
+    C: ConnectionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::restore(&mut call, id).await?;
+    call.finish();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/users/{id}/purge",
+    params(DeleteParams),
+    responses(
+        (status = NO_CONTENT, description = "permanently delete soft deleted user"),
+        (status = NOT_FOUND, description = "deleted user not found"),
+        (status = UNPROCESSABLE_ENTITY, description = "user still has roles and dependents is block")
+    ),
+    tag = USER_TAG,
+    security(
+        ("Bearer" = [])
+    )
+)]
+pub async fn users_purge<C>(
+    State(state): State<Arc<WebState<C>>>,
+    Extension(scope): Extension<DataScope>,
+    audit: Audit,
+    Path(id): Path<i64>,
+    Query(params): Query<DeleteParams>,
+) -> Result<StatusCode, AppError>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut call = Call::new(&state, &scope, &audit);
+    ops::purge(&mut call, id, params.dependents).await?;
+    call.finish();
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,31 +1,49 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
-use validator::Validate;
+use validator::{Validate, ValidateArgs, ValidationError, ValidationErrors};
 
-use crate::entity::UserModel;
+use crate::{
+    entity::{RoleModel, UserModel},
+    service::{
+        password_policy::{PasswordPolicy, not_username, validate_password},
+        query::ListQuery,
+        user::UserFilter,
+    },
+};
 
-#[derive(Deserialize, ToSchema, Validate)]
-pub struct ListRequest {
-    #[validate(range(min = 1, message = "page must be greater than 0"))]
-    pub page: u64,
-    #[validate(range(
-        min = 1,
-        max = 100,
-        message = "page_size must be greater than 0 and less than 100"
-    ))]
-    pub page_size: u64,
-}
+/// 关键字、过滤和排序字段见 [`user::list`](crate::service::user::list)
+pub type ListRequest = ListQuery<UserFilter>;
 
 #[derive(Serialize, ToSchema)]
 pub struct User {
     pub id: i64,
     pub username: String,
+    pub status: i16,
+    pub dept_id: Option<i64>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub nickname: Option<String>,
+    pub avatar: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// 最近一次登录成功的时间
+    pub last_login_at: Option<DateTime<Utc>>,
 }
 impl From<UserModel> for User {
     fn from(user: UserModel) -> Self {
         User {
             id: user.id,
             username: user.name,
+            status: user.status,
+            dept_id: user.dept_id,
+            email: user.email,
+            phone: user.phone,
+            nickname: user.nickname,
+            avatar: user.avatar,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+            last_login_at: user.last_login_at,
         }
     }
 }
@@ -33,24 +51,148 @@ impl From<UserModel> for User {
 #[derive(Serialize, ToSchema)]
 pub struct ListResponse {
     pub users: Vec<User>,
+    /// 满足条件的总数，按游标分页时不统计
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    pub page: u64,
+    pub page_size: u64,
+    /// 下一页的游标，只在按游标分页且还有数据时返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Validate)]
+#[validate(context = PasswordPolicy)]
+#[validate(schema(function = "create_password_not_username"))]
 pub struct CreateRequest {
     pub username: String,
+    #[validate(custom(function = "validate_password", use_context))]
     pub password: String,
+    /// 找回密码使用的邮箱
+    #[serde(default)]
+    #[validate(email)]
+    pub email: Option<String>,
+    #[serde(default)]
+    #[validate(custom(function = "validate_phone"))]
+    pub phone: Option<String>,
+    /// 显示名称
+    #[serde(default)]
+    #[validate(length(max = 50))]
+    pub nickname: Option<String>,
+    /// 头像地址
+    #[serde(default)]
+    #[validate(url, length(max = 255))]
+    pub avatar: Option<String>,
+}
+
+fn create_password_not_username(request: &CreateRequest) -> Result<(), ValidationError> {
+    not_username(&request.username, &request.password)
+}
+
+/// 手机号可以带国际区号的 `+` 前缀，数字之间允许空格和 `-`
+pub(crate) fn validate_phone(phone: &str) -> Result<(), ValidationError> {
+    let digits = phone.chars().filter(char::is_ascii_digit).count();
+    let valid = (5..=20).contains(&phone.len())
+        && digits >= 5
+        && phone
+            .strip_prefix('+')
+            .unwrap_or(phone)
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == ' ' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(ValidationError::new("phone").with_message("invalid phone number".into()))
+    }
 }
 
+/// `/user/update` 和 `user.update` 的参数
 #[derive(Deserialize, ToSchema)]
 pub struct UpdateRequest {
     pub id: i64,
+    #[serde(flatten)]
+    pub fields: UpdateFields,
+}
+
+/// 校验需要密码策略，派生的 `nested` 不支持带参数的校验，直接交给 `fields`
+impl<'v> ValidateArgs<'v> for UpdateRequest {
+    type Args = &'v PasswordPolicy;
+
+    fn validate_with_args(&self, args: Self::Args) -> Result<(), ValidationErrors> {
+        self.fields.validate_with_args(args)
+    }
+}
+
+/// 要修改的字段，`PATCH /users/{id}` 的请求体，id 取自路径；
+/// 请求中没有用户名时，新密码与现有用户名的比较在接口中进行
+#[derive(Deserialize, ToSchema, Validate)]
+#[validate(context = PasswordPolicy)]
+#[validate(schema(function = "update_password_not_username"))]
+pub struct UpdateFields {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub username: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[validate(custom(function = "validate_password", use_context))]
     pub password: Option<String>,
+    /// 用户状态（0正常 1停用 2锁定 3待激活），改为非正常状态时注销其所有会话
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[validate(range(min = 0, max = 3, message = "status must be between 0 and 3"))]
+    pub status: Option<i16>,
+    /// 所属部门，用于数据权限
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dept_id: Option<i64>,
+    /// 找回密码使用的邮箱
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[validate(email)]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[validate(custom(function = "validate_phone"))]
+    pub phone: Option<String>,
+    /// 显示名称
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[validate(length(max = 50))]
+    pub nickname: Option<String>,
+    /// 头像地址
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[validate(url, length(max = 255))]
+    pub avatar: Option<String>,
+}
+
+fn update_password_not_username(request: &UpdateFields) -> Result<(), ValidationError> {
+    match (&request.username, &request.password) {
+        (Some(username), Some(password)) => not_username(username, password),
+        _ => Ok(()),
+    }
 }
 
 #[derive(Serialize, ToSchema)]
 pub struct GetResponse {
     pub user: User,
 }
+
+#[derive(Serialize, ToSchema)]
+pub struct Role {
+    pub id: i32,
+    pub name: String,
+    pub status: i16,
+}
+impl From<RoleModel> for Role {
+    fn from(role: RoleModel) -> Self {
+        Role {
+            id: role.id,
+            name: role.name,
+            status: role.status,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RolesResponse {
+    pub roles: Vec<Role>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AssignRolesRequest {
+    pub user_id: i64,
+    pub role_ids: Vec<i32>,
+}
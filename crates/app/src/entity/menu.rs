@@ -9,7 +9,14 @@ pub struct Model {
     pub id: i32,
     pub name: String,
     pub path: String,
+    pub perms: String,
     pub is_frame: bool,
+    pub parent_id: Option<i32>,
+    pub sort_order: i32,
+    pub icon: String,
+    pub menu_type: i16,
+    pub visible: bool,
+    pub deleted_at: Option<DateTimeUtc>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -30,8 +30,37 @@ pub use user_role::{
     Model as UserRoleModel,
 };
 
+mod dept;
+pub use dept::{
+    ActiveModel as DeptActiveModel, Column as DeptColumn, Entity as DeptEntity, Model as DeptModel,
+};
+
+mod role_dept;
+pub use role_dept::{
+    ActiveModel as RoleDeptActiveModel, Column as RoleDeptColumn, Entity as RoleDeptEntity,
+    Model as RoleDeptModel,
+};
+
 mod online;
 pub use online::{
     ActiveModel as OnlineActiveModel, Column as OnlineColumn, Entity as OnlineEntity,
     Model as OnlineModel,
 };
+
+mod password_history;
+pub use password_history::{
+    ActiveModel as PasswordHistoryActiveModel, Column as PasswordHistoryColumn,
+    Entity as PasswordHistoryEntity, Model as PasswordHistoryModel,
+};
+
+mod password_reset;
+pub use password_reset::{
+    ActiveModel as PasswordResetActiveModel, Column as PasswordResetColumn,
+    Entity as PasswordResetEntity, Model as PasswordResetModel,
+};
+
+mod audit_log;
+pub use audit_log::{
+    ActiveModel as AuditLogActiveModel, Column as AuditLogColumn, Entity as AuditLogEntity,
+    Model as AuditLogModel,
+};
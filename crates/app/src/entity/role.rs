@@ -10,16 +10,26 @@ pub struct Model {
     pub name: String,
     pub data_scope: i16,
     pub status: i16,
+    pub is_superuser: bool,
+    pub deleted_at: Option<DateTimeUtc>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::role_dept::Entity")]
+    RoleDept,
     #[sea_orm(has_many = "super::role_menu::Entity")]
     RoleMenu,
     #[sea_orm(has_many = "super::user_role::Entity")]
     UserRole,
 }
 
+impl Related<super::role_dept::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RoleDept.def()
+    }
+}
+
 impl Related<super::role_menu::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::RoleMenu.def()
@@ -41,6 +51,15 @@ impl Related<super::menu::Entity> for Entity {
     }
 }
 
+impl Related<super::dept::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::role_dept::Relation::Dept.def()
+    }
+    fn via() -> Option<RelationDef> {
+        Some(super::role_dept::Relation::Role.def().rev())
+    }
+}
+
 impl Related<super::user::Entity> for Entity {
     fn to() -> RelationDef {
         super::user_role::Relation::User.def()
@@ -10,22 +10,64 @@ pub struct Model {
     #[sea_orm(unique)]
     pub name: String,
     pub password: String,
+    pub status: i16,
+    pub dept_id: Option<i64>,
+    pub perm_version: i32,
+    #[sea_orm(unique)]
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub nickname: Option<String>,
+    pub avatar: Option<String>,
+    pub created_at: DateTimeUtc,
+    pub updated_at: DateTimeUtc,
+    pub last_login_at: Option<DateTimeUtc>,
+    pub deleted_at: Option<DateTimeUtc>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::dept::Entity",
+        from = "Column::DeptId",
+        to = "super::dept::Column::Id",
+        on_update = "NoAction",
+        on_delete = "NoAction"
+    )]
+    Dept,
     #[sea_orm(has_many = "super::online::Entity")]
     Online,
+    #[sea_orm(has_many = "super::password_history::Entity")]
+    PasswordHistory,
+    #[sea_orm(has_many = "super::password_reset::Entity")]
+    PasswordReset,
     #[sea_orm(has_many = "super::user_role::Entity")]
     UserRole,
 }
 
+impl Related<super::dept::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Dept.def()
+    }
+}
+
 impl Related<super::online::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Online.def()
     }
 }
 
+impl Related<super::password_history::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PasswordHistory.def()
+    }
+}
+
+impl Related<super::password_reset::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PasswordReset.def()
+    }
+}
+
 impl Related<super::user_role::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::UserRole.def()
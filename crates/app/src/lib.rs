@@ -8,11 +8,11 @@ pub mod web_state;
 
 pub mod controller;
 
-use std::{net::SocketAddr, sync::Arc};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 
 use anyhow::Result;
 use axum::middleware::{self};
-use sea_orm::ConnectionTrait;
+use sea_orm::{ConnectionTrait, TransactionTrait};
 use tokio::net::TcpListener;
 use utoipa::{
     OpenApi,
@@ -26,13 +26,17 @@ use utoipa_swagger_ui::SwaggerUi;
 
 use crate::{
     controller::router,
+    service::{online, password_reset},
     web_state::{ApiDoc, WebState, log_request},
 };
 
 pub async fn app_start<C>(addr: &SocketAddr, state: Arc<WebState<C>>) -> Result<()>
 where
-    C: ConnectionTrait + Send + Sync + 'static,
+    C: ConnectionTrait + TransactionTrait + Send + Sync + 'static,
 {
+    state.session.validate()?;
+    tokio::spawn(reap_expired_sessions(state.clone()));
+
     let mut components = Components::new();
     components.security_schemes.insert(
         "Bearer".to_string(),
@@ -54,9 +58,36 @@ where
     let listener = TcpListener::bind(addr).await?;
     tracing::info!("Listening on http://{addr}");
 
-    axum::serve(listener, app).await?;
+    // 登录限流需要客户端地址
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
     Ok(())
 }
 
+async fn reap_expired_sessions<C>(state: Arc<WebState<C>>)
+where
+    C: ConnectionTrait,
+{
+    // 启动时已检查间隔不为 0，否则 `interval` 会 panic
+    let period = Duration::from_secs(state.session.reap_interval);
+    let mut interval = tokio::time::interval(period);
+    loop {
+        interval.tick().await;
+        match online::delete_expired(&state.db).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Reaped {count} expired sessions"),
+            Err(e) => tracing::error!("Reap expired sessions error: {e}"),
+        }
+        match password_reset::delete_expired(&state.db).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Reaped {count} used or expired password reset tokens"),
+            Err(e) => tracing::error!("Reap password reset tokens error: {e}"),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests;
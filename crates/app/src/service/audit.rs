@@ -0,0 +1,198 @@
+use anyhow::{Context as _, Result};
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveValue::{NotSet, Set},
+    ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, QueryTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+
+use super::query::{Cursor, ListQuery, Page, Paging, fetch_after, fetch_page};
+use crate::entity::{AuditLogActiveModel, AuditLogColumn, AuditLogEntity, AuditLogModel};
+
+/// 产生审计事件的请求
+#[derive(Debug, Clone, Default)]
+pub struct Context {
+    /// 当前登录的用户，未登录时为空
+    pub actor_id: Option<i64>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+}
+
+/// 一条审计事件，`action` 形如 `user.update`、`auth.login`
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub action: &'static str,
+    pub target_type: Option<&'static str>,
+    pub target_id: Option<String>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl Entry {
+    pub fn new(action: &'static str) -> Self {
+        Self {
+            action,
+            target_type: None,
+            target_id: None,
+            before: None,
+            after: None,
+        }
+    }
+
+    pub fn target(mut self, target_type: &'static str, target_id: impl ToString) -> Self {
+        self.target_type = Some(target_type);
+        self.target_id = Some(target_id.to_string());
+        self
+    }
+
+    /// 新建的记录
+    pub fn created(mut self, after: &impl Serialize) -> Self {
+        self.after = snapshot(after);
+        self
+    }
+
+    /// 删除前的记录
+    pub fn deleted(mut self, before: &impl Serialize) -> Self {
+        self.before = snapshot(before);
+        self
+    }
+
+    /// 修改前后的记录，两者都是对象时只保留值发生变化的字段
+    pub fn changed(mut self, before: &impl Serialize, after: &impl Serialize) -> Self {
+        (self.before, self.after) = diff(snapshot(before), snapshot(after));
+        self
+    }
+
+    /// 与记录无关的附加信息，例如登录失败的原因，记录在 `after` 中
+    pub fn detail(mut self, detail: Value) -> Self {
+        self.after = Some(detail);
+        self
+    }
+}
+
+fn snapshot(value: &impl Serialize) -> Option<Value> {
+    match serde_json::to_value(value) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            tracing::warn!("serialize audit snapshot error: {}", e);
+            None
+        }
+    }
+}
+
+/// 去掉前后相同的字段，不是对象时原样返回
+fn diff(before: Option<Value>, after: Option<Value>) -> (Option<Value>, Option<Value>) {
+    let (Some(Value::Object(mut before)), Some(Value::Object(mut after))) =
+        (before.clone(), after.clone())
+    else {
+        return (before, after);
+    };
+    let unchanged: Vec<String> = before
+        .iter()
+        .filter(|(key, value)| after.get(*key) == Some(*value))
+        .map(|(key, _)| key.clone())
+        .collect();
+    for key in unchanged {
+        before.remove(&key);
+        after.remove(&key);
+    }
+    let object = |map: Map<String, Value>| Some(Value::Object(map));
+    (object(before), object(after))
+}
+
+pub async fn record<C: ConnectionTrait>(db: &C, context: &Context, entry: Entry) -> Result<()> {
+    AuditLogEntity::insert(AuditLogActiveModel {
+        id: NotSet,
+        actor_id: Set(context.actor_id),
+        action: Set(entry.action.to_string()),
+        target_type: Set(entry.target_type.map(str::to_string)),
+        target_id: Set(entry.target_id),
+        before: Set(entry.before),
+        after: Set(entry.after),
+        ip: Set(context.ip.clone()),
+        user_agent: Set(context.user_agent.clone()),
+        request_id: Set(context.request_id.clone()),
+        created_at: Set(Utc::now()),
+    })
+    .exec(db)
+    .await
+    .map(|_| ())
+    .context("record audit log error")
+}
+
+/// 审计日志列表的过滤条件
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct AuditFilter {
+    pub actor_id: Option<i64>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub ip: Option<String>,
+    pub request_id: Option<String>,
+    /// 记录时间不早于该时间
+    pub created_from: Option<DateTime<Utc>>,
+    /// 记录时间早于该时间
+    pub created_to: Option<DateTime<Utc>>,
+}
+
+/// 审计日志列表允许的排序字段
+const SORT_FIELDS: [(&str, AuditLogColumn); 4] = [
+    ("id", AuditLogColumn::Id),
+    ("actor_id", AuditLogColumn::ActorId),
+    ("action", AuditLogColumn::Action),
+    ("created_at", AuditLogColumn::CreatedAt),
+];
+
+/// 关键字匹配操作和客户端标识
+pub async fn list<C: ConnectionTrait>(
+    db: &C,
+    query: &ListQuery<AuditFilter>,
+) -> Result<Page<AuditLogModel>> {
+    let filter = &query.filter;
+    let select = AuditLogEntity::find()
+        .filter(query.keyword_condition(&[AuditLogColumn::Action, AuditLogColumn::UserAgent]))
+        .apply_if(filter.actor_id, |select, actor_id| {
+            select.filter(AuditLogColumn::ActorId.eq(actor_id))
+        })
+        .apply_if(filter.action.as_ref(), |select, action| {
+            select.filter(AuditLogColumn::Action.eq(action))
+        })
+        .apply_if(filter.target_type.as_ref(), |select, target_type| {
+            select.filter(AuditLogColumn::TargetType.eq(target_type))
+        })
+        .apply_if(filter.target_id.as_ref(), |select, target_id| {
+            select.filter(AuditLogColumn::TargetId.eq(target_id))
+        })
+        .apply_if(filter.ip.as_ref(), |select, ip| {
+            select.filter(AuditLogColumn::Ip.eq(ip))
+        })
+        .apply_if(filter.request_id.as_ref(), |select, request_id| {
+            select.filter(AuditLogColumn::RequestId.eq(request_id))
+        })
+        .apply_if(filter.created_from, |select, from| {
+            select.filter(AuditLogColumn::CreatedAt.gte(from))
+        })
+        .apply_if(filter.created_to, |select, to| {
+            select.filter(AuditLogColumn::CreatedAt.lt(to))
+        });
+    let page = match query.paging()? {
+        Paging::Offset => {
+            let sort = query.sort_column(&SORT_FIELDS, AuditLogColumn::Id)?;
+            let select = select
+                .order_by(sort, query.order.into())
+                .order_by_asc(AuditLogColumn::Id);
+            fetch_page(db, select, query).await
+        }
+        Paging::Cursor(after) => {
+            let after = after.map(|after| after.id::<i64>()).transpose()?;
+            let select = select
+                .apply_if(after, |select, id| select.filter(AuditLogColumn::Id.gt(id)))
+                .order_by_asc(AuditLogColumn::Id);
+            fetch_after(db, select, query, |log| Cursor::new(log.id as u64)).await
+        }
+    };
+    page.context("list audit log error")
+}
@@ -0,0 +1,169 @@
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        Mutex, MutexGuard,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+
+use super::{data_scope::DataScope, permission::Permissions};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheConfig {
+    /// 最多缓存的令牌数，为 0 时不缓存
+    pub capacity: usize,
+    /// 缓存有效期（秒），兜底未能主动失效的修改
+    pub ttl: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            ttl: 60,
+        }
+    }
+}
+
+/// 一个令牌加载出的权限和数据范围
+#[derive(Debug, Clone)]
+pub struct CachedPermissions {
+    pub user_id: i64,
+    /// 加载时用户拥有的角色，用于按角色失效
+    pub role_ids: Vec<i32>,
+    pub permissions: Permissions,
+    pub scope: DataScope,
+}
+
+struct Entry {
+    value: CachedPermissions,
+    loaded_at: Instant,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub size: usize,
+    pub capacity: usize,
+}
+
+/// 令牌到权限的进程内缓存
+///
+/// 修改角色、菜单、用户角色、角色菜单或删除会话后由调用方主动失效相关条目；
+/// 失效前已开始加载的请求可能写回旧数据，由 `ttl` 兜底
+pub struct PermissionCache {
+    entries: Option<Mutex<LruCache<String, Entry>>>,
+    ttl: Duration,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl Default for PermissionCache {
+    fn default() -> Self {
+        Self::new(&CacheConfig::default())
+    }
+}
+
+impl PermissionCache {
+    pub fn new(config: &CacheConfig) -> Self {
+        Self {
+            entries: NonZeroUsize::new(config.capacity).map(|cap| Mutex::new(LruCache::new(cap))),
+            ttl: Duration::from_secs(config.ttl),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// 条目属于其他用户（令牌被重新分配）或已过期时视为未命中
+    pub fn get(&self, token: &str, user_id: i64) -> Option<CachedPermissions> {
+        let value = self.lock().and_then(|mut entries| {
+            let fresh = entries.get(token).is_some_and(|entry| {
+                entry.value.user_id == user_id && entry.loaded_at.elapsed() < self.ttl
+            });
+            if fresh {
+                entries.get(token).map(|entry| entry.value.clone())
+            } else {
+                entries.pop(token);
+                None
+            }
+        });
+
+        let counter = if value.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+
+    pub fn insert(&self, token: &str, value: CachedPermissions) {
+        if let Some(mut entries) = self.lock() {
+            entries.put(
+                token.to_string(),
+                Entry {
+                    value,
+                    loaded_at: Instant::now(),
+                },
+            );
+        }
+    }
+
+    pub fn invalidate_token(&self, token: &str) {
+        if let Some(mut entries) = self.lock() {
+            entries.pop(token);
+        }
+    }
+
+    pub fn invalidate_user(&self, user_id: i64) {
+        self.invalidate_where(|value| value.user_id == user_id);
+    }
+
+    pub fn invalidate_role(&self, role_id: i32) {
+        self.invalidate_where(|value| value.role_ids.contains(&role_id));
+    }
+
+    /// 菜单的修改无法在内存中对应到角色，直接清空
+    pub fn clear(&self) {
+        if let Some(mut entries) = self.lock() {
+            entries.clear();
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let (size, capacity) = self
+            .lock()
+            .map_or((0, 0), |entries| (entries.len(), entries.cap().get()));
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            size,
+            capacity,
+        }
+    }
+
+    fn invalidate_where(&self, matches: impl Fn(&CachedPermissions) -> bool) {
+        if let Some(mut entries) = self.lock() {
+            let tokens: Vec<String> = entries
+                .iter()
+                .filter(|(_, entry)| matches(&entry.value))
+                .map(|(token, _)| token.clone())
+                .collect();
+            for token in tokens {
+                entries.pop(&token);
+            }
+        }
+    }
+
+    /// 持锁期间不会 panic，锁中毒时仍然沿用其中的数据
+    fn lock(&self) -> Option<MutexGuard<'_, LruCache<String, Entry>>> {
+        self.entries
+            .as_ref()
+            .map(|entries| entries.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
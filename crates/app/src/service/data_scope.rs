@@ -0,0 +1,155 @@
+use std::collections::BTreeSet;
+
+use anyhow::{Context, Result, anyhow};
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, EntityTrait, QueryFilter, QuerySelect, Select,
+};
+
+use super::role::STATUS_NORMAL;
+use crate::entity::{
+    RoleColumn, RoleDeptColumn, RoleDeptEntity, RoleEntity, UserColumn, UserEntity, UserRoleColumn,
+    UserRoleEntity,
+};
+
+/// `role.data_scope` 的取值
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    /// 全部数据
+    All,
+    /// `role_dept` 中配置的部门
+    Custom,
+    /// 本部门
+    Dept,
+    /// 仅本人
+    Own,
+}
+
+impl Scope {
+    pub const fn value(self) -> i16 {
+        match self {
+            Scope::All => 0,
+            Scope::Custom => 1,
+            Scope::Dept => 2,
+            Scope::Own => 3,
+        }
+    }
+}
+
+impl TryFrom<i16> for Scope {
+    type Error = anyhow::Error;
+
+    fn try_from(value: i16) -> Result<Self> {
+        match value {
+            0 => Ok(Scope::All),
+            1 => Ok(Scope::Custom),
+            2 => Ok(Scope::Dept),
+            3 => Ok(Scope::Own),
+            _ => Err(anyhow!("invalid data scope: {}", value)),
+        }
+    }
+}
+
+/// 参与数据范围过滤的实体，需要指出行所属的部门和所有者
+pub trait Scoped: EntityTrait {
+    fn dept_column() -> Self::Column;
+    fn owner_column() -> Self::Column;
+}
+
+impl Scoped for UserEntity {
+    fn dept_column() -> Self::Column {
+        UserColumn::DeptId
+    }
+
+    fn owner_column() -> Self::Column {
+        UserColumn::Id
+    }
+}
+
+/// 调用者可以访问的数据范围，由其所有正常状态角色的 `data_scope` 合并而来
+#[derive(Debug, Clone, Default)]
+pub struct DataScope {
+    all: bool,
+    user_id: Option<i64>,
+    dept_ids: BTreeSet<i64>,
+}
+
+impl DataScope {
+    /// 不受限制，用于超级管理员和内部调用
+    pub fn all() -> Self {
+        Self {
+            all: true,
+            ..Default::default()
+        }
+    }
+
+    /// 部门是否在范围内，仅本人的范围不包含任何部门
+    pub fn covers_dept(&self, dept_id: i64) -> bool {
+        self.all || self.dept_ids.contains(&dept_id)
+    }
+
+    /// 给查询加上数据范围条件，范围为空时查询不到任何数据
+    pub fn apply<E: Scoped>(&self, select: Select<E>) -> Select<E> {
+        if self.all {
+            return select;
+        }
+        let mut condition = Condition::any();
+        if !self.dept_ids.is_empty() {
+            condition = condition.add(E::dept_column().is_in(self.dept_ids.iter().copied()));
+        }
+        if let Some(user_id) = self.user_id {
+            condition = condition.add(E::owner_column().eq(user_id));
+        }
+        select.filter(condition)
+    }
+}
+
+/// 加载用户的数据范围，停用的角色和无法识别的 `data_scope` 不授予任何数据
+pub async fn load<C: ConnectionTrait>(db: &C, user_id: i64) -> Result<DataScope> {
+    let roles = RoleEntity::find()
+        .inner_join(UserRoleEntity)
+        .filter(UserRoleColumn::UserId.eq(user_id))
+        .filter(RoleColumn::Status.eq(STATUS_NORMAL))
+        .filter(RoleColumn::DeletedAt.is_null())
+        .all(db)
+        .await
+        .context("load data scope error")?;
+
+    let mut scope = DataScope::default();
+    let mut own_dept = false;
+    let mut custom_roles = Vec::new();
+    for role in roles {
+        match Scope::try_from(role.data_scope) {
+            Ok(Scope::All) => return Ok(DataScope::all()),
+            Ok(Scope::Custom) => custom_roles.push(i64::from(role.id)),
+            Ok(Scope::Dept) => own_dept = true,
+            Ok(Scope::Own) => scope.user_id = Some(user_id),
+            Err(e) => tracing::warn!("role {}: {}", role.id, e),
+        }
+    }
+
+    if own_dept {
+        let dept_id = UserEntity::find_by_id(user_id)
+            .select_only()
+            .column(UserColumn::DeptId)
+            .into_tuple::<Option<i64>>()
+            .one(db)
+            .await
+            .context("load data scope error")?
+            .flatten();
+        scope.dept_ids.extend(dept_id);
+    }
+
+    if !custom_roles.is_empty() {
+        let dept_ids = RoleDeptEntity::find()
+            .select_only()
+            .column(RoleDeptColumn::DeptId)
+            .filter(RoleDeptColumn::RoleId.is_in(custom_roles))
+            .into_tuple::<i64>()
+            .all(db)
+            .await
+            .context("load data scope error")?;
+        scope.dept_ids.extend(dept_ids);
+    }
+
+    Ok(scope)
+}
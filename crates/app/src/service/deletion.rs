@@ -0,0 +1,79 @@
+use anyhow::{Context, Result};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait, QueryFilter,
+    sea_query::{IntoCondition, SimpleExpr},
+};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use super::error::HasDependents;
+
+/// 删除用户、角色或菜单时如何处理仍关联它的记录（`user_role`、`role_menu`、`role_dept`、下级菜单）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Dependents {
+    /// 存在关联记录时拒绝删除
+    Block,
+    /// 软删除时保留关联记录，恢复后重新生效，下级菜单一起软删除；
+    /// 彻底删除时一起删除关联记录和下级菜单
+    #[default]
+    Cascade,
+    /// 删除关联记录，下级菜单移到顶层
+    Detach,
+}
+
+/// `deleted` 为真时只保留已软删除的记录，否则只保留未删除的记录
+pub fn deleted_condition(column: impl ColumnTrait, deleted: bool) -> SimpleExpr {
+    if deleted {
+        column.is_not_null()
+    } else {
+        column.is_null()
+    }
+}
+
+/// 按 `dependents` 处理关联表 `E` 中满足 `condition` 的记录，`owner` 为被删除的记录：
+/// `Block` 时存在关联记录即返回 [`HasDependents`]，`Detach` 时删除关联记录，
+/// `Cascade` 时只在彻底删除（`purge`）时删除关联记录
+pub(super) async fn unlink<E, C>(
+    db: &C,
+    owner: (&'static str, i64),
+    condition: impl IntoCondition,
+    dependents: Dependents,
+    purge: bool,
+) -> Result<()>
+where
+    E: EntityTrait,
+    E::Model: Sync,
+    C: ConnectionTrait,
+{
+    let condition = condition.into_condition();
+    let table = E::default().table_name().to_string();
+    match dependents {
+        Dependents::Block => {
+            let count = E::find()
+                .filter(condition)
+                .count(db)
+                .await
+                .with_context(|| format!("count {} error", table))?;
+            if count > 0 {
+                let (entity, id) = owner;
+                return Err(HasDependents {
+                    entity,
+                    id,
+                    dependents: table,
+                    count,
+                }
+                .into());
+            }
+        }
+        Dependents::Cascade if !purge => {}
+        Dependents::Cascade | Dependents::Detach => {
+            E::delete_many()
+                .filter(condition)
+                .exec(db)
+                .await
+                .with_context(|| format!("delete {} error", table))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use anyhow::{Context, Result};
+use sea_orm::{
+    ActiveValue::{NotSet, Set},
+    ConnectionTrait, EntityTrait, QueryOrder,
+};
+
+use crate::entity::{DeptActiveModel, DeptColumn, DeptEntity, DeptModel};
+
+pub async fn create<C: ConnectionTrait>(db: &C, name: &str) -> Result<DeptModel> {
+    DeptEntity::insert(DeptActiveModel {
+        id: NotSet,
+        name: Set(name.to_string()),
+    })
+    .exec_with_returning(db)
+    .await
+    .context("create dept error")
+}
+
+pub async fn get<C: ConnectionTrait>(db: &C, id: i64) -> Result<Option<DeptModel>> {
+    DeptEntity::find_by_id(id)
+        .one(db)
+        .await
+        .context("get dept error")
+}
+
+pub async fn list<C: ConnectionTrait>(db: &C) -> Result<Vec<DeptModel>> {
+    DeptEntity::find()
+        .order_by_asc(DeptColumn::Id)
+        .all(db)
+        .await
+        .context("list dept error")
+}
@@ -0,0 +1,141 @@
+use std::fmt;
+
+use sea_orm::{DbErr, SqlErr};
+
+/// 引用的记录不存在，例如给用户分配了不存在的角色
+#[derive(Debug)]
+pub struct MissingReferences {
+    pub entity: &'static str,
+    pub ids: Vec<i64>,
+}
+
+impl fmt::Display for MissingReferences {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} not found: {:?}", self.entity, self.ids)
+    }
+}
+
+impl std::error::Error for MissingReferences {}
+
+/// 删除时仍有关联的记录，并且请求选择了 [`Dependents::Block`](super::deletion::Dependents::Block)
+#[derive(Debug)]
+pub struct HasDependents {
+    pub entity: &'static str,
+    pub id: i64,
+    /// 关联记录所在的表，例如 `user_role`
+    pub dependents: String,
+    pub count: u64,
+}
+
+impl fmt::Display for HasDependents {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} is still referenced by {} {} rows",
+            self.entity, self.id, self.count, self.dependents
+        )
+    }
+}
+
+impl std::error::Error for HasDependents {}
+
+/// 把记录挂到自身或其后代下面会形成环
+#[derive(Debug)]
+pub struct ParentCycle {
+    pub entity: &'static str,
+    pub id: i64,
+    pub parent_id: i64,
+}
+
+impl fmt::Display for ParentCycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} cannot be the parent of {}",
+            self.entity, self.parent_id, self.id
+        )
+    }
+}
+
+impl std::error::Error for ParentCycle {}
+
+/// 新密码与最近设置过的某个密码相同
+#[derive(Debug)]
+pub struct PasswordReused {
+    pub depth: usize,
+}
+
+impl fmt::Display for PasswordReused {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "password must differ from the last {} passwords",
+            self.depth
+        )
+    }
+}
+
+impl std::error::Error for PasswordReused {}
+
+/// 违反唯一约束，例如用户名或邮箱已被其他用户使用
+#[derive(Debug)]
+pub struct Conflict {
+    pub entity: &'static str,
+    pub field: &'static str,
+}
+
+impl Conflict {
+    /// 从唯一约束错误中识别冲突的字段：各数据库的错误信息都带有列名或以列名命名的索引名，
+    /// `fields` 按顺序匹配，识别不出时返回 `None`
+    pub fn from_db(e: &DbErr, entity: &'static str, fields: &[&'static str]) -> Option<Self> {
+        let Some(SqlErr::UniqueConstraintViolation(message)) = e.sql_err() else {
+            return None;
+        };
+        fields
+            .iter()
+            .find(|field| message.contains(*field))
+            .map(|field| Self { entity, field })
+    }
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} already exists", self.entity, self.field)
+    }
+}
+
+impl std::error::Error for Conflict {}
+
+/// 排序字段不在列表允许的范围内
+#[derive(Debug)]
+pub struct InvalidSort {
+    pub field: String,
+    pub allowed: Vec<&'static str>,
+}
+
+impl fmt::Display for InvalidSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot sort by `{}`, expected one of: {}",
+            self.field,
+            self.allowed.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for InvalidSort {}
+
+/// 列表游标无法解析，或与游标分页不支持的参数同时使用
+#[derive(Debug)]
+pub struct InvalidCursor {
+    pub reason: &'static str,
+}
+
+impl fmt::Display for InvalidCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid cursor: {}", self.reason)
+    }
+}
+
+impl std::error::Error for InvalidCursor {}
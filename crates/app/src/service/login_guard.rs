@@ -0,0 +1,298 @@
+use std::{
+    net::IpAddr,
+    num::NonZeroUsize,
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 最多跟踪的用户名和 IP 数量，超出后淘汰最久未失败的记录
+const TRACKED_KEYS: usize = 10_000;
+
+/// 最多同时有效的验证码数量，超出后淘汰最早签发的
+const PENDING_CHALLENGES: usize = 10_000;
+
+/// 一类键（用户名或 IP）的退避和锁定策略
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Policy {
+    /// 不受限制的连续失败次数
+    pub free_attempts: u32,
+    /// 超出后第一次退避的时长（秒），之后每次失败翻倍
+    pub base_delay: u64,
+    /// 退避的最长时长（秒）
+    pub max_delay: u64,
+    /// 连续失败达到该次数后锁定，为 0 时不锁定
+    pub lockout_threshold: u32,
+    /// 锁定时长（秒）
+    pub lockout_duration: u64,
+    /// 连续失败达到该次数后要求验证码，为 0 时不要求
+    pub challenge_after: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginGuardConfig {
+    pub user: Policy,
+    /// 同一 IP 会尝试多个用户名，阈值应比 `user` 宽松
+    pub ip: Policy,
+    /// 用户名不存在和密码错误返回同一个错误码
+    pub generic_error: bool,
+    /// 最后一次失败之后多久（秒）清除失败记录
+    pub reset_after: u64,
+}
+
+impl Default for LoginGuardConfig {
+    fn default() -> Self {
+        Self {
+            user: Policy {
+                free_attempts: 3,
+                base_delay: 1,
+                max_delay: 60,
+                lockout_threshold: 10,
+                lockout_duration: 15 * 60,
+                challenge_after: 3,
+            },
+            ip: Policy {
+                free_attempts: 10,
+                base_delay: 1,
+                max_delay: 60,
+                lockout_threshold: 50,
+                lockout_duration: 15 * 60,
+                challenge_after: 10,
+            },
+            generic_error: false,
+            reset_after: 60 * 60,
+        }
+    }
+}
+
+/// 登录前的检查结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// 可以尝试登录，`challenge` 为真时需要先通过验证码
+    Allowed { challenge: bool },
+    /// 处于退避期
+    Throttled { retry_after: Duration },
+    /// 连续失败次数过多，暂时锁定
+    Locked { retry_after: Duration },
+}
+
+/// 发给客户端的验证码
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Challenge {
+    pub id: String,
+    pub prompt: String,
+}
+
+/// 验证码的签发和校验，接入第三方服务时 `issue` 可以只返回空的 `prompt`
+pub trait ChallengeVerifier: Send + Sync {
+    fn issue(&self) -> Challenge;
+
+    /// 校验答案，无论结果如何验证码都只能使用一次
+    fn verify(&self, id: &str, answer: &str) -> bool;
+}
+
+/// 进程内的算术验证码，用于测试和单机部署
+pub struct MemoryChallenge {
+    pending: Mutex<LruCache<String, (String, Instant)>>,
+    ttl: Duration,
+}
+
+impl Default for MemoryChallenge {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(5 * 60))
+    }
+}
+
+impl MemoryChallenge {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            pending: Mutex::new(LruCache::new(
+                NonZeroUsize::new(PENDING_CHALLENGES).expect("PENDING_CHALLENGES is not zero"),
+            )),
+            ttl,
+        }
+    }
+}
+
+impl ChallengeVerifier for MemoryChallenge {
+    fn issue(&self) -> Challenge {
+        let id = Uuid::new_v4();
+        let bytes = id.as_bytes();
+        let (a, b) = (bytes[0] % 10, bytes[1] % 10);
+
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.put(
+            id.to_string(),
+            ((a + b).to_string(), Instant::now() + self.ttl),
+        );
+
+        Challenge {
+            id: id.to_string(),
+            prompt: format!("{} + {} = ?", a, b),
+        }
+    }
+
+    fn verify(&self, id: &str, answer: &str) -> bool {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending
+            .pop(id)
+            .is_some_and(|(expected, expires_at)| expires_at > Instant::now() && expected == answer)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Key {
+    User(String),
+    Ip(IpAddr),
+}
+
+struct Failures {
+    count: u32,
+    last_failure: Instant,
+}
+
+/// 按用户名和 IP 统计连续登录失败，进程内有效，多实例部署时各自独立计数
+pub struct LoginGuard {
+    config: LoginGuardConfig,
+    failures: Mutex<LruCache<Key, Failures>>,
+    challenge: Option<Box<dyn ChallengeVerifier>>,
+}
+
+impl Default for LoginGuard {
+    fn default() -> Self {
+        Self::new(LoginGuardConfig::default())
+    }
+}
+
+impl LoginGuard {
+    pub fn new(config: LoginGuardConfig) -> Self {
+        Self {
+            config,
+            failures: Mutex::new(LruCache::new(
+                NonZeroUsize::new(TRACKED_KEYS).expect("TRACKED_KEYS is not zero"),
+            )),
+            challenge: None,
+        }
+    }
+
+    pub fn with_challenge(mut self, challenge: Box<dyn ChallengeVerifier>) -> Self {
+        self.challenge = Some(challenge);
+        self
+    }
+
+    pub fn config(&self) -> &LoginGuardConfig {
+        &self.config
+    }
+
+    pub fn challenge(&self) -> Option<&dyn ChallengeVerifier> {
+        self.challenge.as_deref()
+    }
+
+    pub fn check(&self, username: &str, ip: Option<IpAddr>) -> Verdict {
+        self.check_at(username, ip, Instant::now())
+    }
+
+    pub fn record_failure(&self, username: &str, ip: Option<IpAddr>) {
+        self.record_failure_at(username, ip, Instant::now());
+    }
+
+    /// 登录成功只清除用户名的记录，避免攻击者用自己的账号重置 IP 的计数
+    pub fn record_success(&self, username: &str) {
+        self.lock().pop(&Key::User(username.to_string()));
+    }
+
+    pub(crate) fn check_at(&self, username: &str, ip: Option<IpAddr>, now: Instant) -> Verdict {
+        let mut failures = self.lock();
+        let mut verdict = Verdict::Allowed { challenge: false };
+        for (key, policy) in self.keys(username, ip) {
+            let Some(entry) = failures.peek(&key) else {
+                continue;
+            };
+            let elapsed = now.saturating_duration_since(entry.last_failure);
+            if elapsed >= Duration::from_secs(self.config.reset_after) {
+                failures.pop(&key);
+                continue;
+            }
+            verdict = stricter(verdict, Self::verdict(policy, entry.count, elapsed));
+        }
+        verdict
+    }
+
+    pub(crate) fn record_failure_at(&self, username: &str, ip: Option<IpAddr>, now: Instant) {
+        let mut failures = self.lock();
+        for (key, _) in self.keys(username, ip) {
+            let reset_after = Duration::from_secs(self.config.reset_after);
+            let entry = failures.get_or_insert_mut(key, || Failures {
+                count: 0,
+                last_failure: now,
+            });
+            if now.saturating_duration_since(entry.last_failure) >= reset_after {
+                entry.count = 0;
+            }
+            entry.count += 1;
+            entry.last_failure = now;
+        }
+    }
+
+    fn keys(&self, username: &str, ip: Option<IpAddr>) -> Vec<(Key, &Policy)> {
+        let mut keys = vec![(Key::User(username.to_string()), &self.config.user)];
+        keys.extend(ip.map(|ip| (Key::Ip(ip), &self.config.ip)));
+        keys
+    }
+
+    fn verdict(policy: &Policy, count: u32, elapsed: Duration) -> Verdict {
+        if policy.lockout_threshold > 0 && count >= policy.lockout_threshold {
+            let lockout = Duration::from_secs(policy.lockout_duration);
+            if elapsed < lockout {
+                return Verdict::Locked {
+                    retry_after: lockout - elapsed,
+                };
+            }
+        } else if count > policy.free_attempts {
+            let exponent = (count - policy.free_attempts - 1).min(31);
+            let delay = policy
+                .base_delay
+                .saturating_mul(1 << exponent)
+                .min(policy.max_delay);
+            let delay = Duration::from_secs(delay);
+            if elapsed < delay {
+                return Verdict::Throttled {
+                    retry_after: delay - elapsed,
+                };
+            }
+        }
+
+        let challenge = policy.challenge_after > 0 && count >= policy.challenge_after;
+        Verdict::Allowed { challenge }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, LruCache<Key, Failures>> {
+        self.failures.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// 锁定优先于退避，同类取等待时间较长的
+fn stricter(a: Verdict, b: Verdict) -> Verdict {
+    match (a, b) {
+        (Verdict::Locked { retry_after: x }, Verdict::Locked { retry_after: y }) => {
+            Verdict::Locked {
+                retry_after: x.max(y),
+            }
+        }
+        (locked @ Verdict::Locked { .. }, _) | (_, locked @ Verdict::Locked { .. }) => locked,
+        (Verdict::Throttled { retry_after: x }, Verdict::Throttled { retry_after: y }) => {
+            Verdict::Throttled {
+                retry_after: x.max(y),
+            }
+        }
+        (throttled @ Verdict::Throttled { .. }, _) | (_, throttled @ Verdict::Throttled { .. }) => {
+            throttled
+        }
+        (Verdict::Allowed { challenge: x }, Verdict::Allowed { challenge: y }) => {
+            Verdict::Allowed { challenge: x || y }
+        }
+    }
+}
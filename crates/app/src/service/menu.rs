@@ -1,69 +1,560 @@
-use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use sea_orm::{
     ActiveValue::{NotSet, Set},
-    ConnectionTrait, EntityTrait, QueryOrder, QuerySelect,
+    ColumnTrait, Condition, ConnectionTrait, EntityTrait, JoinType, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, QueryTrait, Related, TransactionTrait,
+    sea_query::{Expr, IntoCondition},
 };
+use serde::Deserialize;
+use utoipa::ToSchema;
 
-use crate::entity::{MenuActiveModel, MenuColumn, MenuEntity, MenuModel};
+use super::{
+    deletion::{self, Dependents},
+    error::{HasDependents, MissingReferences, ParentCycle},
+    query::{Cursor, ListQuery, Page, Paging, fetch_after, fetch_page},
+    role::STATUS_NORMAL,
+    user,
+};
+use crate::entity::{
+    MenuActiveModel, MenuColumn, MenuEntity, MenuModel, RoleColumn, RoleEntity, RoleMenuColumn,
+    RoleMenuEntity, UserRoleColumn, UserRoleEntity,
+};
+
+/// 菜单类型：目录
+pub const TYPE_DIRECTORY: i16 = 0;
+/// 菜单类型：页面
+pub const TYPE_PAGE: i16 = 1;
+/// 菜单类型：按钮（只用于授权）
+pub const TYPE_BUTTON: i16 = 2;
+
+/// 菜单的层级和展示属性
+#[derive(Debug, Clone)]
+pub struct Layout {
+    pub parent_id: Option<i32>,
+    pub sort_order: i32,
+    pub icon: String,
+    pub menu_type: i16,
+    pub visible: bool,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            parent_id: None,
+            sort_order: 0,
+            icon: String::new(),
+            menu_type: TYPE_PAGE,
+            visible: true,
+        }
+    }
+}
+
+/// 需要修改的层级和展示属性，`parent_id` 为 `Some(None)` 时移到顶层
+#[derive(Debug, Clone, Default)]
+pub struct LayoutUpdate {
+    pub parent_id: Option<Option<i32>>,
+    pub sort_order: Option<i32>,
+    pub icon: Option<String>,
+    pub menu_type: Option<i16>,
+    pub visible: Option<bool>,
+}
+
+/// 菜单树的节点，同级按 `sort_order`、`id` 排序
+#[derive(Debug, Clone)]
+pub struct MenuNode {
+    pub menu: MenuModel,
+    pub children: Vec<MenuNode>,
+}
 
 pub async fn create<C: ConnectionTrait>(
     db: &C,
     name: &str,
     path: &str,
+    perms: &str,
     is_frame: bool,
+    layout: Layout,
 ) -> Result<MenuModel> {
+    if let Some(parent_id) = layout.parent_id {
+        check_parent(db, None, parent_id).await?;
+    }
+
     MenuEntity::insert(MenuActiveModel {
         id: NotSet,
         name: Set(name.to_string()),
         path: Set(path.to_string()),
+        perms: Set(perms.to_string()),
         is_frame: Set(is_frame),
+        parent_id: Set(layout.parent_id),
+        sort_order: Set(layout.sort_order),
+        icon: Set(layout.icon),
+        menu_type: Set(layout.menu_type),
+        visible: Set(layout.visible),
+        deleted_at: NotSet,
     })
     .exec_with_returning(db)
     .await
-    .map_err(|e| anyhow::anyhow!("create role error: {}", e))
+    .context("create role error")
+}
+
+/// 软删除菜单并使通过角色获得该菜单的用户已签发的访问令牌失效；
+/// 角色关联和下级菜单按 `dependents` 处理：`Cascade` 保留角色关联并一起软删除所有下级，
+/// `Detach` 删除角色关联并把直接下级移到顶层，`Block` 在存在角色关联或未删除的下级时拒绝
+pub async fn delete<C>(db: &C, id: i32, dependents: Dependents) -> Result<()>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let txn = db.begin().await?;
+    let mut ids = vec![id];
+    match dependents {
+        Dependents::Block => {
+            let children = MenuEntity::find()
+                .filter(MenuColumn::ParentId.eq(id))
+                .filter(MenuColumn::DeletedAt.is_null())
+                .count(&txn)
+                .await
+                .context("count menu children error")?;
+            if children > 0 {
+                return Err(HasDependents {
+                    entity: "menu",
+                    id: i64::from(id),
+                    dependents: "menu".to_string(),
+                    count: children,
+                }
+                .into());
+            }
+        }
+        Dependents::Cascade => {
+            ids.extend(descendants(&txn, id, MenuColumn::DeletedAt.is_null()).await?);
+        }
+        Dependents::Detach => detach_children(&txn, id).await?,
+    }
+
+    for menu_id in &ids {
+        user::bump_perm_version(&txn, user::with_menu(*menu_id)).await?;
+    }
+    unlink(&txn, id, dependents, false).await?;
+    // 同一次删除的菜单使用相同的删除时间，恢复时据此一起恢复
+    MenuEntity::update_many()
+        .col_expr(MenuColumn::DeletedAt, Expr::value(Utc::now()))
+        .filter(MenuColumn::Id.is_in(ids))
+        .filter(MenuColumn::DeletedAt.is_null())
+        .exec(&txn)
+        .await
+        .context("delete menu error")?;
+    txn.commit().await?;
+    Ok(())
+}
+
+/// 恢复软删除的菜单，以及和它在同一次删除中被删除的下级；
+/// 上级菜单仍处于删除状态时返回 [`MissingReferences`]
+pub async fn restore<C>(db: &C, id: i32) -> Result<()>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let Some(menu) = get_deleted(db, id).await? else {
+        return Ok(());
+    };
+    if let Some(parent_id) = menu.parent_id
+        && get(db, parent_id).await?.is_none()
+    {
+        return Err(MissingReferences {
+            entity: "menu",
+            ids: vec![i64::from(parent_id)],
+        }
+        .into());
+    }
+
+    let txn = db.begin().await?;
+    let mut ids = vec![id];
+    ids.extend(descendants(&txn, id, MenuColumn::DeletedAt.eq(menu.deleted_at)).await?);
+    MenuEntity::update_many()
+        .col_expr(
+            MenuColumn::DeletedAt,
+            Expr::value(Option::<DateTime<Utc>>::None),
+        )
+        .filter(MenuColumn::Id.is_in(ids.clone()))
+        .exec(&txn)
+        .await
+        .context("restore menu error")?;
+    for menu_id in ids {
+        user::bump_perm_version(&txn, user::with_menu(menu_id)).await?;
+    }
+    txn.commit().await?;
+    Ok(())
+}
+
+/// 彻底删除菜单，角色关联和下级菜单按 `dependents` 处理：`Cascade` 一起彻底删除所有下级，
+/// `Detach` 把直接下级移到顶层，`Block` 在存在角色关联或任何下级（包括已软删除的）时拒绝
+pub async fn purge<C>(db: &C, id: i32, dependents: Dependents) -> Result<()>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let txn = db.begin().await?;
+    let mut ids = vec![id];
+    match dependents {
+        Dependents::Block => {
+            let children = MenuEntity::find()
+                .filter(MenuColumn::ParentId.eq(id))
+                .count(&txn)
+                .await
+                .context("count menu children error")?;
+            if children > 0 {
+                return Err(HasDependents {
+                    entity: "menu",
+                    id: i64::from(id),
+                    dependents: "menu".to_string(),
+                    count: children,
+                }
+                .into());
+            }
+        }
+        Dependents::Cascade => ids.extend(descendants(&txn, id, Condition::all()).await?),
+        Dependents::Detach => detach_children(&txn, id).await?,
+    }
+
+    for menu_id in &ids {
+        user::bump_perm_version(&txn, user::with_menu(*menu_id)).await?;
+        unlink(&txn, *menu_id, dependents, true).await?;
+    }
+    MenuEntity::delete_many()
+        .filter(MenuColumn::Id.is_in(ids))
+        .exec(&txn)
+        .await
+        .context("purge menu error")?;
+    txn.commit().await?;
+    Ok(())
+}
+
+async fn unlink<C: ConnectionTrait>(
+    db: &C,
+    id: i32,
+    dependents: Dependents,
+    purge: bool,
+) -> Result<()> {
+    deletion::unlink::<RoleMenuEntity, _>(
+        db,
+        ("menu", i64::from(id)),
+        RoleMenuColumn::MenuId.eq(i64::from(id)),
+        dependents,
+        purge,
+    )
+    .await
 }
 
-pub async fn delete<C: ConnectionTrait>(db: &C, id: i32) -> Result<()> {
-    MenuEntity::delete_by_id(id)
+/// 把直接下级移到顶层
+async fn detach_children<C: ConnectionTrait>(db: &C, id: i32) -> Result<()> {
+    MenuEntity::update_many()
+        .col_expr(MenuColumn::ParentId, Expr::value(Option::<i32>::None))
+        .filter(MenuColumn::ParentId.eq(id))
         .exec(db)
         .await
         .map(|_| ())
-        .map_err(|e| anyhow::anyhow!("delete role error: {}", e))
+        .context("detach menu children error")
+}
+
+/// `id` 的所有后代，只沿满足 `condition` 的菜单向下查找
+async fn descendants<C: ConnectionTrait>(
+    db: &C,
+    id: i32,
+    condition: impl IntoCondition,
+) -> Result<Vec<i32>> {
+    let menus: Vec<(i32, Option<i32>)> = MenuEntity::find()
+        .select_only()
+        .column(MenuColumn::Id)
+        .column(MenuColumn::ParentId)
+        .filter(MenuColumn::ParentId.is_not_null())
+        .filter(condition)
+        .into_tuple()
+        .all(db)
+        .await
+        .context("list menu descendants error")?;
+
+    let mut children: HashMap<i32, Vec<i32>> = HashMap::new();
+    for (menu_id, parent_id) in menus {
+        children
+            .entry(parent_id.unwrap_or_default())
+            .or_default()
+            .push(menu_id);
+    }
+    let mut visited = HashSet::from([id]);
+    let mut ids = Vec::new();
+    let mut stack = vec![id];
+    while let Some(parent_id) = stack.pop() {
+        for &child in children.get(&parent_id).into_iter().flatten() {
+            // 已有数据中存在环时同样能结束
+            if visited.insert(child) {
+                ids.push(child);
+                stack.push(child);
+            }
+        }
+    }
+    Ok(ids)
 }
 
+/// 不存在或已删除的菜单返回 [`DbErr::RecordNotUpdated`](sea_orm::DbErr::RecordNotUpdated)
 pub async fn update<C: ConnectionTrait>(
     db: &C,
     id: i32,
     name: Option<String>,
     path: Option<String>,
+    perms: Option<String>,
     is_frame: Option<bool>,
+    layout: LayoutUpdate,
 ) -> Result<()> {
+    if let Some(Some(parent_id)) = layout.parent_id {
+        check_parent(db, Some(id), parent_id).await?;
+    }
+
+    let bump = perms.is_some();
     MenuEntity::update(MenuActiveModel {
         id: Set(id),
         name: name.map(Set).unwrap_or(NotSet),
         path: path.map(Set).unwrap_or(NotSet),
+        perms: perms.map(Set).unwrap_or(NotSet),
         is_frame: is_frame.map(Set).unwrap_or(NotSet),
+        parent_id: layout.parent_id.map(Set).unwrap_or(NotSet),
+        sort_order: layout.sort_order.map(Set).unwrap_or(NotSet),
+        icon: layout.icon.map(Set).unwrap_or(NotSet),
+        menu_type: layout.menu_type.map(Set).unwrap_or(NotSet),
+        visible: layout.visible.map(Set).unwrap_or(NotSet),
+        deleted_at: NotSet,
     })
+    .filter(MenuColumn::DeletedAt.is_null())
     .exec(db)
     .await
-    .map(|_| ())
-    .map_err(|e| anyhow::anyhow!("update role error: {}", e))
+    .context("update menu error")?;
+
+    // 权限码变化会使拥有该菜单的用户已签发的访问令牌失效
+    if bump {
+        user::bump_perm_version(db, user::with_menu(id)).await?;
+    }
+    Ok(())
 }
 
 pub async fn get<C: ConnectionTrait>(db: &C, id: i32) -> Result<Option<MenuModel>> {
     MenuEntity::find_by_id(id)
+        .filter(MenuColumn::DeletedAt.is_null())
         .one(db)
         .await
-        .map_err(|e| anyhow::anyhow!("get role error: {}", e))
+        .context("get role error")
+}
+
+/// 已软删除的菜单
+pub async fn get_deleted<C: ConnectionTrait>(db: &C, id: i32) -> Result<Option<MenuModel>> {
+    MenuEntity::find_by_id(id)
+        .filter(MenuColumn::DeletedAt.is_not_null())
+        .one(db)
+        .await
+        .context("get menu error")
+}
+
+/// 菜单列表的过滤条件
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct MenuFilter {
+    pub is_frame: Option<bool>,
+    pub menu_type: Option<i16>,
+    pub visible: Option<bool>,
+    pub parent_id: Option<i32>,
+    /// 只列出已软删除的菜单
+    #[serde(default)]
+    pub deleted: bool,
+}
+
+/// 菜单列表允许的排序字段
+const SORT_FIELDS: [(&str, MenuColumn); 4] = [
+    ("id", MenuColumn::Id),
+    ("name", MenuColumn::Name),
+    ("path", MenuColumn::Path),
+    ("sort_order", MenuColumn::SortOrder),
+];
+
+/// 关键字匹配菜单名称、路径和权限标识
+pub async fn list<C: ConnectionTrait>(
+    db: &C,
+    query: &ListQuery<MenuFilter>,
+) -> Result<Page<MenuModel>> {
+    let filter = &query.filter;
+    let select = MenuEntity::find()
+        .filter(deletion::deleted_condition(
+            MenuColumn::DeletedAt,
+            filter.deleted,
+        ))
+        .filter(query.keyword_condition(&[MenuColumn::Name, MenuColumn::Path, MenuColumn::Perms]))
+        .apply_if(filter.is_frame, |select, is_frame| {
+            select.filter(MenuColumn::IsFrame.eq(is_frame))
+        })
+        .apply_if(filter.menu_type, |select, menu_type| {
+            select.filter(MenuColumn::MenuType.eq(menu_type))
+        })
+        .apply_if(filter.visible, |select, visible| {
+            select.filter(MenuColumn::Visible.eq(visible))
+        })
+        .apply_if(filter.parent_id, |select, parent_id| {
+            select.filter(MenuColumn::ParentId.eq(parent_id))
+        });
+    let page = match query.paging()? {
+        Paging::Offset => {
+            let sort = query.sort_column(&SORT_FIELDS, MenuColumn::Id)?;
+            let select = select
+                .order_by(sort, query.order.into())
+                .order_by_asc(MenuColumn::Id);
+            fetch_page(db, select, query).await
+        }
+        Paging::Cursor(after) => {
+            let after = after.map(|after| after.id::<i32>()).transpose()?;
+            let select = select
+                .apply_if(after, |select, id| select.filter(MenuColumn::Id.gt(id)))
+                .order_by_asc(MenuColumn::Id);
+            fetch_after(db, select, query, |menu| Cursor::new(menu.id as u64)).await
+        }
+    };
+    page.context("list menu error")
 }
 
-pub async fn list<C: ConnectionTrait>(db: &C, page: u64, page_size: u64) -> Result<Vec<MenuModel>> {
-    let offset = (page - 1) * page_size;
-    MenuEntity::find()
+/// 检查 `parent_id` 存在，并且不是 `id` 自身或其后代
+async fn check_parent<C: ConnectionTrait>(db: &C, id: Option<i32>, parent_id: i32) -> Result<()> {
+    let cycle = || ParentCycle {
+        entity: "menu",
+        id: i64::from(id.unwrap_or_default()),
+        parent_id: i64::from(parent_id),
+    };
+
+    let mut visited = HashSet::new();
+    let mut current = Some(parent_id);
+    while let Some(menu_id) = current {
+        if Some(menu_id) == id {
+            return Err(cycle().into());
+        }
+        // 已有数据中存在环时同样拒绝，避免死循环
+        if !visited.insert(menu_id) {
+            return Err(cycle().into());
+        }
+        let parent = MenuEntity::find_by_id(menu_id)
+            .filter(MenuColumn::DeletedAt.is_null())
+            .select_only()
+            .column(MenuColumn::ParentId)
+            .into_tuple::<Option<i32>>()
+            .one(db)
+            .await
+            .context("get menu parent error")?;
+        match parent {
+            Some(parent) => current = parent,
+            None if menu_id == parent_id => {
+                return Err(MissingReferences {
+                    entity: "menu",
+                    ids: vec![i64::from(parent_id)],
+                }
+                .into());
+            }
+            None => current = None,
+        }
+    }
+    Ok(())
+}
+
+/// 菜单树；`user_id` 不为空时只保留该用户被授予的菜单及其上级目录，
+/// `visible_only` 时去掉隐藏的菜单及其下级
+pub async fn tree<C: ConnectionTrait>(
+    db: &C,
+    user_id: Option<i64>,
+    visible_only: bool,
+) -> Result<Vec<MenuNode>> {
+    let menus = MenuEntity::find()
+        .filter(MenuColumn::DeletedAt.is_null())
+        .order_by_asc(MenuColumn::SortOrder)
         .order_by_asc(MenuColumn::Id)
-        .offset(offset)
-        .limit(page_size)
         .all(db)
         .await
-        .map_err(|e| anyhow::anyhow!("list role error: {}", e))
+        .context("list menu tree error")?;
+
+    let granted = match user_id {
+        Some(user_id) => Some(granted_with_ancestors(db, &menus, user_id).await?),
+        None => None,
+    };
+
+    Ok(prune(build_tree(menus), &|menu| {
+        (menu.visible || !visible_only) && granted.as_ref().is_none_or(|ids| ids.contains(&menu.id))
+    }))
+}
+
+/// 用户通过正常状态角色被授予的菜单，加上它们的所有上级
+async fn granted_with_ancestors<C: ConnectionTrait>(
+    db: &C,
+    menus: &[MenuModel],
+    user_id: i64,
+) -> Result<HashSet<i32>> {
+    let granted: Vec<i32> = MenuEntity::find()
+        .select_only()
+        .column(MenuColumn::Id)
+        .distinct()
+        .inner_join(RoleEntity)
+        .join(
+            JoinType::InnerJoin,
+            <RoleEntity as Related<UserRoleEntity>>::to(),
+        )
+        .filter(UserRoleColumn::UserId.eq(user_id))
+        .filter(RoleColumn::Status.eq(STATUS_NORMAL))
+        .filter(RoleColumn::DeletedAt.is_null())
+        .filter(MenuColumn::DeletedAt.is_null())
+        .into_tuple()
+        .all(db)
+        .await
+        .context("list granted menu error")?;
+
+    let parents: HashMap<i32, Option<i32>> = menus.iter().map(|m| (m.id, m.parent_id)).collect();
+    let mut ids = HashSet::new();
+    for id in granted {
+        let mut current = Some(id);
+        while let Some(id) = current {
+            if !ids.insert(id) {
+                break;
+            }
+            current = parents.get(&id).copied().flatten();
+        }
+    }
+    Ok(ids)
+}
+
+/// 去掉不满足 `keep` 的节点及其下级
+fn prune(nodes: Vec<MenuNode>, keep: &impl Fn(&MenuModel) -> bool) -> Vec<MenuNode> {
+    nodes
+        .into_iter()
+        .filter(|node| keep(&node.menu))
+        .map(|node| MenuNode {
+            menu: node.menu,
+            children: prune(node.children, keep),
+        })
+        .collect()
+}
+
+/// `menus` 需已按同级顺序排好，父节点不在 `menus` 中的菜单作为顶层节点
+fn build_tree(menus: Vec<MenuModel>) -> Vec<MenuNode> {
+    let ids: HashSet<i32> = menus.iter().map(|menu| menu.id).collect();
+    let mut children: HashMap<Option<i32>, Vec<MenuModel>> = HashMap::new();
+    for menu in menus {
+        let parent_id = menu.parent_id.filter(|id| ids.contains(id));
+        children.entry(parent_id).or_default().push(menu);
+    }
+
+    fn attach(
+        parent_id: Option<i32>,
+        children: &mut HashMap<Option<i32>, Vec<MenuModel>>,
+    ) -> Vec<MenuNode> {
+        children
+            .remove(&parent_id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|menu| {
+                let id = menu.id;
+                MenuNode {
+                    menu,
+                    children: attach(Some(id), children),
+                }
+            })
+            .collect()
+    }
+    attach(None, &mut children)
 }
@@ -1,4 +1,21 @@
+pub mod audit;
+pub mod cache;
+pub mod data_scope;
+pub mod deletion;
+pub mod dept;
+pub mod error;
+pub mod login_guard;
 pub mod menu;
+pub mod notifier;
 pub mod online;
+pub mod password;
+pub mod password_policy;
+pub mod password_reset;
+pub mod permission;
+pub mod query;
 pub mod role;
+pub mod role_dept;
+pub mod role_menu;
+pub mod token;
 pub mod user;
+pub mod user_role;
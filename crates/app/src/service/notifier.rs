@@ -0,0 +1,179 @@
+use std::path::PathBuf;
+
+use anyhow::{Result, anyhow, bail};
+use async_trait::async_trait;
+use chrono::Utc;
+use tokio::{
+    fs::OpenOptions,
+    io::{AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+
+/// 发给用户的一条通知
+#[derive(Debug, Clone)]
+pub struct Message {
+    /// 收件地址
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// 通知的投递方式，例如找回密码的邮件
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn send(&self, message: &Message) -> Result<()>;
+}
+
+/// 把通知写入日志或追加到文件，用于本地开发和测试
+#[derive(Debug, Clone, Default)]
+pub struct LogNotifier {
+    file: Option<PathBuf>,
+}
+
+impl LogNotifier {
+    pub fn to_file(path: PathBuf) -> Self {
+        Self { file: Some(path) }
+    }
+}
+
+#[async_trait]
+impl Notifier for LogNotifier {
+    async fn send(&self, message: &Message) -> Result<()> {
+        let Some(path) = &self.file else {
+            tracing::info!(
+                "notify {}: {}\n{}",
+                message.to,
+                message.subject,
+                message.body
+            );
+            return Ok(());
+        };
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+            .map_err(|e| anyhow!("open notify file {} error: {}", path.display(), e))?;
+        let entry = format!(
+            "Date: {}\nTo: {}\nSubject: {}\n\n{}\n\n",
+            Utc::now().to_rfc3339(),
+            message.to,
+            message.subject,
+            message.body
+        );
+        // tokio 的文件在后台线程写入，需要 flush 才能确认写完
+        async {
+            file.write_all(entry.as_bytes()).await?;
+            file.flush().await
+        }
+        .await
+        .map_err(|e| anyhow!("write notify file {} error: {}", path.display(), e))
+    }
+}
+
+/// 通过 SMTP 投递的邮件通知
+///
+/// 只实现明文 SMTP，不支持 TLS 和认证，用于本机的邮件中继或 MailHog 之类的测试邮箱
+#[derive(Debug, Clone)]
+pub struct SmtpNotifier {
+    /// 服务器地址，例如 `127.0.0.1:1025`
+    pub addr: String,
+    /// 发件地址
+    pub from: String,
+}
+
+impl SmtpNotifier {
+    pub fn new(addr: impl Into<String>, from: impl Into<String>) -> Self {
+        Self {
+            addr: addr.into(),
+            from: from.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for SmtpNotifier {
+    async fn send(&self, message: &Message) -> Result<()> {
+        // 地址和标题写在命令和邮件头里，不能包含换行
+        for field in [&self.from, &message.to, &message.subject] {
+            if field.contains(['\r', '\n']) {
+                bail!("invalid line break in mail header: {:?}", field);
+            }
+        }
+
+        let stream = TcpStream::connect(&self.addr)
+            .await
+            .map_err(|e| anyhow!("connect smtp server {} error: {}", self.addr, e))?;
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+
+        expect_reply(&mut reader, 220).await?;
+        command(&mut reader, &mut writer, "EHLO localhost", 250).await?;
+        command(
+            &mut reader,
+            &mut writer,
+            &format!("MAIL FROM:<{}>", self.from),
+            250,
+        )
+        .await?;
+        command(
+            &mut reader,
+            &mut writer,
+            &format!("RCPT TO:<{}>", message.to),
+            250,
+        )
+        .await?;
+        command(&mut reader, &mut writer, "DATA", 354).await?;
+
+        let mut data = format!(
+            "From: <{}>\r\nTo: <{}>\r\nSubject: {}\r\nDate: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n",
+            self.from,
+            message.to,
+            message.subject,
+            Utc::now().to_rfc2822()
+        );
+        // 以 `.` 开头的行需要再加一个 `.`，避免被当成结束符
+        for line in message.body.lines() {
+            if line.starts_with('.') {
+                data.push('.');
+            }
+            data.push_str(line);
+            data.push_str("\r\n");
+        }
+        data.push_str(".\r\n");
+        writer.write_all(data.as_bytes()).await?;
+        expect_reply(&mut reader, 250).await?;
+
+        command(&mut reader, &mut writer, "QUIT", 221).await
+    }
+}
+
+async fn command<R, W>(reader: &mut R, writer: &mut W, line: &str, expected: u16) -> Result<()>
+where
+    R: AsyncBufReadExt + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    writer.write_all(format!("{}\r\n", line).as_bytes()).await?;
+    expect_reply(reader, expected).await
+}
+
+/// 读取一个应答，多行应答以 `250-` 形式续行，最后一行为 `250 `
+async fn expect_reply<R>(reader: &mut R, expected: u16) -> Result<()>
+where
+    R: AsyncBufReadExt + Unpin,
+{
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            bail!("smtp server closed the connection");
+        }
+        let code = line.get(..3).and_then(|code| code.parse::<u16>().ok());
+        if code != Some(expected) {
+            bail!("unexpected smtp reply: {}", line.trim_end());
+        }
+        if line.as_bytes().get(3) != Some(&b'-') {
+            return Ok(());
+        }
+    }
+}
@@ -1,11 +1,83 @@
-use anyhow::Result;
+use std::time::Duration;
+
+use anyhow::{Context, Result, ensure};
+use chrono::{DateTime, Utc};
 use sea_orm::{
-    ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder,
-    QuerySelect, Statement,
+    ActiveValue::{NotSet, Set},
+    ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, QueryTrait,
+    sea_query::{Expr, IntoCondition, Query},
 };
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-use crate::entity::{OnlineActiveModel, OnlineColumn, OnlineEntity, OnlineModel};
+use super::query::{Cursor, ListQuery, Page, Paging, fetch_after, fetch_page};
+use crate::entity::{
+    OnlineActiveModel, OnlineColumn, OnlineEntity, OnlineModel, UserColumn, UserEntity, UserModel,
+    UserRoleColumn, UserRoleEntity,
+};
+
+/// 空闲超时和绝对有效期的上限（秒），更大的值加到当前时间上会超出时间戳的范围
+pub const MAX_TIMEOUT: u64 = 100 * 365 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionConfig {
+    /// 空闲超时（秒），每次请求都会把过期时间顺延到 `now + idle_timeout`
+    pub idle_timeout: u64,
+    /// 绝对有效期（秒），从登录开始计算，顺延不会超过该时间
+    pub absolute_timeout: u64,
+    /// 后台清理过期会话的间隔（秒），不能为 0
+    pub reap_interval: u64,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            idle_timeout: 2 * 60 * 60,
+            absolute_timeout: 24 * 60 * 60,
+            reap_interval: 5 * 60,
+        }
+    }
+}
+
+impl SessionConfig {
+    /// 超时不能超过 [`MAX_TIMEOUT`]，清理间隔不能为 0
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            self.idle_timeout <= MAX_TIMEOUT && self.absolute_timeout <= MAX_TIMEOUT,
+            "session timeout must not exceed {MAX_TIMEOUT} seconds"
+        );
+        ensure!(
+            self.reap_interval > 0,
+            "session reap interval must not be 0"
+        );
+        Ok(())
+    }
+
+    fn expires_at(&self, created_at: DateTime<Utc>, now: DateTime<Utc>) -> DateTime<Utc> {
+        let idle = now + Duration::from_secs(self.idle_timeout);
+        let absolute = created_at + Duration::from_secs(self.absolute_timeout);
+        idle.min(absolute)
+    }
+}
+
+pub enum Session {
+    Valid(OnlineModel),
+    Expired,
+    NotFound,
+}
+
+/// 刷新令牌的轮换结果
+pub enum Refresh {
+    /// 旧令牌已作废，返回同一组中新签发的令牌
+    Rotated(OnlineModel),
+    /// 已轮换过的令牌被再次使用，整组刷新令牌已被注销
+    Reused {
+        user_id: i64,
+    },
+    Expired,
+    NotFound,
+}
 
 pub fn generate_token() -> String {
     let uuid = Uuid::new_v4();
@@ -13,7 +85,31 @@ pub fn generate_token() -> String {
     token
 }
 
-pub async fn create<C: ConnectionTrait>(db: &C, user_id: i64) -> Result<OnlineModel> {
+pub async fn create<C: ConnectionTrait>(
+    db: &C,
+    config: &SessionConfig,
+    user_id: i64,
+) -> Result<OnlineModel> {
+    insert(db, config, user_id, None, Utc::now()).await
+}
+
+/// 登录时签发一组新的刷新令牌中的第一个，有效期与会话相同
+pub async fn create_refresh<C: ConnectionTrait>(
+    db: &C,
+    config: &SessionConfig,
+    user_id: i64,
+) -> Result<OnlineModel> {
+    insert(db, config, user_id, Some(generate_token()), Utc::now()).await
+}
+
+/// 同一组刷新令牌共用 `created_at`，轮换不会超过登录时起算的绝对有效期
+async fn insert<C: ConnectionTrait>(
+    db: &C,
+    config: &SessionConfig,
+    user_id: i64,
+    family: Option<String>,
+    created_at: DateTime<Utc>,
+) -> Result<OnlineModel> {
     loop {
         let token = generate_token();
         if OnlineEntity::find()
@@ -22,9 +118,16 @@ pub async fn create<C: ConnectionTrait>(db: &C, user_id: i64) -> Result<OnlineMo
             .await?
             .is_none()
         {
+            let now = Utc::now();
             let online = OnlineEntity::insert(OnlineActiveModel {
+                id: NotSet,
                 token: Set(token.clone()),
                 user_id: Set(user_id),
+                created_at: Set(created_at),
+                last_seen_at: Set(now),
+                expires_at: Set(config.expires_at(created_at, now)),
+                family: Set(family),
+                rotated_at: Set(None),
             })
             .exec_with_returning(db)
             .await?;
@@ -34,11 +137,172 @@ pub async fn create<C: ConnectionTrait>(db: &C, user_id: i64) -> Result<OnlineMo
 }
 
 pub async fn delete<C: ConnectionTrait>(db: &C, token: &str) -> Result<()> {
-    OnlineEntity::delete_by_id(token)
+    OnlineEntity::delete_many()
+        .filter(OnlineColumn::Token.eq(token))
         .exec(db)
         .await
         .map(|_| ())
-        .map_err(|e| anyhow::anyhow!("delete online error: {}", e))
+        .context("delete online error")
+}
+
+/// 校验会话是否有效，有效时顺延过期时间，过期时删除会话；刷新令牌不能当作会话使用
+pub async fn validate<C: ConnectionTrait>(
+    db: &C,
+    config: &SessionConfig,
+    token: &str,
+) -> Result<Session> {
+    let Some(online) = get(db, token)
+        .await?
+        .filter(|online| online.family.is_none())
+    else {
+        return Ok(Session::NotFound);
+    };
+
+    let now = Utc::now();
+    if online.expires_at <= now {
+        delete(db, token).await?;
+        return Ok(Session::Expired);
+    }
+
+    let online = OnlineEntity::update(OnlineActiveModel {
+        id: Set(online.id),
+        token: NotSet,
+        user_id: NotSet,
+        created_at: NotSet,
+        last_seen_at: Set(now),
+        expires_at: Set(config.expires_at(online.created_at, now)),
+        family: NotSet,
+        rotated_at: NotSet,
+    })
+    .exec(db)
+    .await
+    .context("refresh online error")?;
+
+    Ok(Session::Valid(online))
+}
+
+/// 用刷新令牌换取同一组中的新令牌，旧令牌保留到该组的绝对有效期结束，用于发现重复使用
+pub async fn rotate<C: ConnectionTrait>(
+    db: &C,
+    config: &SessionConfig,
+    token: &str,
+) -> Result<Refresh> {
+    let Some(online) = get(db, token).await? else {
+        return Ok(Refresh::NotFound);
+    };
+    let Some(family) = online.family else {
+        return Ok(Refresh::NotFound);
+    };
+
+    if online.rotated_at.is_some() {
+        delete_family(db, &family).await?;
+        return Ok(Refresh::Reused {
+            user_id: online.user_id,
+        });
+    }
+
+    let now = Utc::now();
+    if online.expires_at <= now {
+        delete(db, token).await?;
+        return Ok(Refresh::Expired);
+    }
+
+    // 条件更新，并发使用同一个令牌时只有一个请求能轮换成功
+    let deadline = online.created_at + Duration::from_secs(config.absolute_timeout);
+    let rotated = OnlineEntity::update_many()
+        .col_expr(OnlineColumn::RotatedAt, Expr::value(now))
+        .col_expr(OnlineColumn::ExpiresAt, Expr::value(deadline))
+        .filter(OnlineColumn::Token.eq(token))
+        .filter(OnlineColumn::RotatedAt.is_null())
+        .exec(db)
+        .await
+        .context("rotate refresh token error")?;
+    if rotated.rows_affected == 0 {
+        delete_family(db, &family).await?;
+        return Ok(Refresh::Reused {
+            user_id: online.user_id,
+        });
+    }
+
+    let next = insert(db, config, online.user_id, Some(family), online.created_at).await?;
+    Ok(Refresh::Rotated(next))
+}
+
+/// 删除同一组的全部刷新令牌，返回删除的数量
+pub async fn delete_family<C: ConnectionTrait>(db: &C, family: &str) -> Result<u64> {
+    OnlineEntity::delete_many()
+        .filter(OnlineColumn::Family.eq(family))
+        .exec(db)
+        .await
+        .map(|res| res.rows_affected)
+        .context("delete refresh token family error")
+}
+
+/// 删除用户的全部会话，返回删除的数量
+pub async fn delete_by_user<C: ConnectionTrait>(db: &C, user_id: i64) -> Result<u64> {
+    OnlineEntity::delete_many()
+        .filter(OnlineColumn::UserId.eq(user_id))
+        .exec(db)
+        .await
+        .map(|res| res.rows_affected)
+        .context("delete online by user error")
+}
+
+/// 发起请求的会话，注销其他会话时保留
+pub enum Current<'a> {
+    /// 数据库会话的令牌
+    Session(&'a str),
+    /// 访问令牌对应的刷新令牌 family
+    Family(&'a str),
+}
+
+/// 删除用户除 `current` 以外的全部会话，返回删除的数量
+pub async fn delete_others<C: ConnectionTrait>(
+    db: &C,
+    user_id: i64,
+    current: Current<'_>,
+) -> Result<u64> {
+    let others = match current {
+        Current::Session(token) => OnlineColumn::Token.ne(token).into_condition(),
+        Current::Family(family) => OnlineColumn::Family
+            .is_null()
+            .or(OnlineColumn::Family.ne(family))
+            .into_condition(),
+    };
+    OnlineEntity::delete_many()
+        .filter(OnlineColumn::UserId.eq(user_id))
+        .filter(others)
+        .exec(db)
+        .await
+        .map(|res| res.rows_affected)
+        .context("delete other sessions error")
+}
+
+/// 注销拥有该角色的所有用户的会话
+pub async fn delete_by_role<C: ConnectionTrait>(db: &C, role_id: i32) -> Result<u64> {
+    OnlineEntity::delete_many()
+        .filter(
+            OnlineColumn::UserId.in_subquery(
+                Query::select()
+                    .column(UserRoleColumn::UserId)
+                    .from(UserRoleEntity)
+                    .and_where(UserRoleColumn::RoleId.eq(role_id))
+                    .to_owned(),
+            ),
+        )
+        .exec(db)
+        .await
+        .map(|res| res.rows_affected)
+        .context("delete online by role error")
+}
+
+pub async fn delete_expired<C: ConnectionTrait>(db: &C) -> Result<u64> {
+    OnlineEntity::delete_many()
+        .filter(OnlineColumn::ExpiresAt.lte(Utc::now()))
+        .exec(db)
+        .await
+        .map(|res| res.rows_affected)
+        .context("delete expired online error")
 }
 
 pub async fn get<C: ConnectionTrait>(db: &C, token: &str) -> Result<Option<OnlineModel>> {
@@ -46,68 +310,82 @@ pub async fn get<C: ConnectionTrait>(db: &C, token: &str) -> Result<Option<Onlin
         .filter(OnlineColumn::Token.eq(token))
         .one(db)
         .await
-        .map_err(|e| anyhow::anyhow!("get online error: {}", e))
+        .context("get online error")
 }
 
-pub async fn list<C: ConnectionTrait>(
+/// 按会话编号查找，管理接口不接触令牌本身
+pub async fn get_by_id<C: ConnectionTrait>(db: &C, id: i64) -> Result<Option<OnlineModel>> {
+    OnlineEntity::find_by_id(id)
+        .one(db)
+        .await
+        .context("get online error")
+}
+
+pub async fn get_with_user<C: ConnectionTrait>(
     db: &C,
-    page: u64,
-    page_size: u64,
-) -> Result<Vec<OnlineModel>> {
-    let offset = (page - 1) * page_size;
-    OnlineEntity::find()
-        .order_by_asc(OnlineColumn::UserId)
-        .offset(offset)
-        .limit(page_size)
-        .all(db)
+    id: i64,
+) -> Result<Option<(OnlineModel, Option<UserModel>)>> {
+    OnlineEntity::find_by_id(id)
+        .find_also_related(UserEntity)
+        .one(db)
         .await
-        .map_err(|e| anyhow::anyhow!("list online error: {}", e))
+        .context("get online error")
+}
+
+/// 会话列表的过滤条件
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct OnlineFilter {
+    pub user_id: Option<i64>,
+    /// 为真时只列出刷新令牌，为假时只列出数据库会话
+    pub refresh: Option<bool>,
 }
 
-pub async fn get_menu_path_by_token<C: ConnectionTrait>(
+/// 会话列表允许的排序字段
+const SORT_FIELDS: [(&str, OnlineColumn); 4] = [
+    ("user_id", OnlineColumn::UserId),
+    ("created_at", OnlineColumn::CreatedAt),
+    ("last_seen_at", OnlineColumn::LastSeenAt),
+    ("expires_at", OnlineColumn::ExpiresAt),
+];
+
+/// 关键字匹配会话所属用户的用户名
+pub async fn list<C: ConnectionTrait>(
     db: &C,
-    token: &str,
-) -> Result<Vec<String>> {
-    let sql = r#"
-    SELECT m.path
-    FROM online o
-    left join user_role ur on ur.user_id = o.user_id
-    left join role_menu rm on rm.role_id = ur.role_id
-    left join menu m on m.id = rm.menu_id
-    where o.token = $1
-"#;
-
-    let result = db
-        .query_all(Statement::from_sql_and_values(
-            sea_orm::DatabaseBackend::Postgres,
-            sql,
-            vec![token.into()],
-        ))
-        .await?;
-
-    let menus = result
-        .into_iter()
-        .filter_map(|row| row.try_get("", "path").ok())
-        .collect();
-
-    Ok(menus)
-}
-
-pub async fn is_admin_by_token<C: ConnectionTrait>(db: &C, token: &str) -> Result<bool> {
-    let sql = r#"
-    SELECT ur.role_id
-    FROM online o
-    left join user_role ur on ur.user_id = o.user_id
-    where o.token = $1 and ur.role_id = 1
-"#;
-
-    let result = db
-        .query_all(Statement::from_sql_and_values(
-            sea_orm::DatabaseBackend::Postgres,
-            sql,
-            vec![token.into()],
-        ))
-        .await?;
-
-    Ok(!result.is_empty())
+    query: &ListQuery<OnlineFilter>,
+) -> Result<Page<(OnlineModel, Option<UserModel>)>> {
+    let filter = &query.filter;
+    let select = OnlineEntity::find()
+        .find_also_related(UserEntity)
+        .filter(query.keyword_condition(&[UserColumn::Name]))
+        .apply_if(filter.user_id, |select, user_id| {
+            select.filter(OnlineColumn::UserId.eq(user_id))
+        })
+        .apply_if(filter.refresh, |select, refresh| {
+            select.filter(if refresh {
+                OnlineColumn::Family.is_not_null()
+            } else {
+                OnlineColumn::Family.is_null()
+            })
+        });
+    let page = match query.paging()? {
+        Paging::Offset => {
+            let sort = query.sort_column(&SORT_FIELDS, OnlineColumn::UserId)?;
+            let select = select
+                .order_by(sort, query.order.into())
+                .order_by_asc(OnlineColumn::Id);
+            fetch_page(db, select, query).await
+        }
+        Paging::Cursor(after) => {
+            // 按自增编号排序，新登录和轮换出的会话总在末尾，游标中不含令牌
+            let after = after.map(|after| after.id::<i64>()).transpose()?;
+            let select = select
+                .apply_if(after, |select, id| select.filter(OnlineColumn::Id.gt(id)))
+                .order_by_asc(OnlineColumn::Id);
+            fetch_after(db, select, query, |(online, _)| {
+                Cursor::new(online.id as u64)
+            })
+            .await
+        }
+    };
+    page.context("list online error")
 }
@@ -0,0 +1,210 @@
+use anyhow::{Result, anyhow};
+use argon2::{
+    Algorithm, Argon2, Params, Version,
+    password_hash::{
+        Ident, Output, PasswordHash, PasswordHasher as _, PasswordVerifier as _, SaltString,
+        rand_core::OsRng,
+    },
+};
+use sha3::{Digest, Sha3_256};
+
+const ARGON2ID_IDENT: &str = "argon2id";
+const SHA3_256_IDENT: &str = "sha3-256";
+
+/// 只能校验、不再用于生成新哈希的算法（历史数据）
+pub trait PasswordVerifier: Send + Sync {
+    /// 判断存储的哈希串是否由本算法生成
+    fn supports(&self, hash: &str) -> bool;
+
+    /// 常量时间校验密码
+    fn verify(&self, password: &str, hash: &str) -> Result<bool>;
+}
+
+/// 生成 PHC 格式（`$<id>$<params>$<salt>$<hash>`）哈希串的算法
+pub trait PasswordHasher: PasswordVerifier {
+    fn hash(&self, password: &str) -> Result<String>;
+
+    /// 存储的哈希串是否需要用当前算法和参数重新计算
+    fn needs_rehash(&self, hash: &str) -> bool;
+}
+
+/// Argon2id，默认算法
+pub struct Argon2Hasher {
+    params: Params,
+}
+
+impl Default for Argon2Hasher {
+    fn default() -> Self {
+        Self::new(Params::default())
+    }
+}
+
+impl Argon2Hasher {
+    pub fn new(params: Params) -> Self {
+        Self { params }
+    }
+
+    fn argon2(&self) -> Argon2<'static> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())
+    }
+}
+
+impl PasswordVerifier for Argon2Hasher {
+    fn supports(&self, hash: &str) -> bool {
+        PasswordHash::new(hash).is_ok_and(|hash| hash.algorithm.as_str() == ARGON2ID_IDENT)
+    }
+
+    fn verify(&self, password: &str, hash: &str) -> Result<bool> {
+        let hash =
+            PasswordHash::new(hash).map_err(|e| anyhow!("parse password hash error: {}", e))?;
+        match self.argon2().verify_password(password.as_bytes(), &hash) {
+            Ok(()) => Ok(true),
+            Err(argon2::password_hash::Error::Password) => Ok(false),
+            Err(e) => Err(anyhow!("verify password error: {}", e)),
+        }
+    }
+}
+
+impl PasswordHasher for Argon2Hasher {
+    fn hash(&self, password: &str) -> Result<String> {
+        let salt = SaltString::generate(&mut OsRng);
+        self.argon2()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| anyhow!("hash password error: {}", e))
+    }
+
+    fn needs_rehash(&self, hash: &str) -> bool {
+        let Ok(hash) = PasswordHash::new(hash) else {
+            return true;
+        };
+        hash.algorithm.as_str() != ARGON2ID_IDENT
+            || hash.version != Some(Version::V0x13.into())
+            || Params::try_from(&hash).map_or(true, |params| {
+                params.m_cost() != self.params.m_cost()
+                    || params.t_cost() != self.params.t_cost()
+                    || params.p_cost() != self.params.p_cost()
+            })
+    }
+}
+
+/// 加盐 SHA3-256，仅用于兼容旧系统导入的数据
+#[derive(Default)]
+pub struct Sha3Hasher;
+
+impl Sha3Hasher {
+    fn digest(salt: &str, password: &str) -> Result<Output> {
+        let mut hasher = Sha3_256::new();
+        hasher.update(salt.as_bytes());
+        hasher.update(password.as_bytes());
+        Output::new(&hasher.finalize()).map_err(|e| anyhow!("sha3 output error: {}", e))
+    }
+}
+
+impl PasswordVerifier for Sha3Hasher {
+    fn supports(&self, hash: &str) -> bool {
+        PasswordHash::new(hash).is_ok_and(|hash| hash.algorithm.as_str() == SHA3_256_IDENT)
+    }
+
+    fn verify(&self, password: &str, hash: &str) -> Result<bool> {
+        let hash =
+            PasswordHash::new(hash).map_err(|e| anyhow!("parse password hash error: {}", e))?;
+        let (Some(salt), Some(expected)) = (hash.salt, hash.hash) else {
+            return Err(anyhow!("sha3 password hash missing salt or hash"));
+        };
+        // Output 的比较是常量时间的
+        Ok(Self::digest(salt.as_str(), password)? == expected)
+    }
+}
+
+impl PasswordHasher for Sha3Hasher {
+    fn hash(&self, password: &str) -> Result<String> {
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = PasswordHash {
+            algorithm: Ident::new_unwrap(SHA3_256_IDENT),
+            version: None,
+            params: Default::default(),
+            salt: Some(salt.as_salt()),
+            hash: Some(Self::digest(salt.as_str(), password)?),
+        };
+        Ok(hash.to_string())
+    }
+
+    fn needs_rehash(&self, hash: &str) -> bool {
+        !self.supports(hash)
+    }
+}
+
+/// 早期版本直接存储的明文密码
+#[derive(Default)]
+pub struct PlaintextVerifier;
+
+impl PasswordVerifier for PlaintextVerifier {
+    /// 只排除已知算法生成的哈希串，明文本身也可能以 `$` 开头
+    fn supports(&self, hash: &str) -> bool {
+        ![ARGON2ID_IDENT, SHA3_256_IDENT].iter().any(|ident| {
+            hash.strip_prefix('$')
+                .and_then(|rest| rest.strip_prefix(ident))
+                .is_some_and(|rest| rest.starts_with('$'))
+        })
+    }
+
+    fn verify(&self, password: &str, hash: &str) -> Result<bool> {
+        // 比较两者的摘要，避免按长度和前缀提前返回
+        Ok(Sha3Hasher::digest("", password)? == Sha3Hasher::digest("", hash)?)
+    }
+}
+
+/// 用主算法生成哈希，并按哈希串的算法标识选择主算法或历史算法校验
+pub struct ChainedHasher {
+    primary: Box<dyn PasswordHasher>,
+    legacy: Vec<Box<dyn PasswordVerifier>>,
+}
+
+impl Default for ChainedHasher {
+    fn default() -> Self {
+        Self::new(Box::new(Argon2Hasher::default()))
+            .with_legacy(Box::new(Sha3Hasher))
+            .with_legacy(Box::new(PlaintextVerifier))
+    }
+}
+
+impl ChainedHasher {
+    pub fn new(primary: Box<dyn PasswordHasher>) -> Self {
+        Self {
+            primary,
+            legacy: Vec::new(),
+        }
+    }
+
+    pub fn with_legacy(mut self, verifier: Box<dyn PasswordVerifier>) -> Self {
+        self.legacy.push(verifier);
+        self
+    }
+}
+
+impl PasswordVerifier for ChainedHasher {
+    fn supports(&self, hash: &str) -> bool {
+        self.primary.supports(hash) || self.legacy.iter().any(|v| v.supports(hash))
+    }
+
+    fn verify(&self, password: &str, hash: &str) -> Result<bool> {
+        if self.primary.supports(hash) {
+            return self.primary.verify(password, hash);
+        }
+        match self.legacy.iter().find(|v| v.supports(hash)) {
+            Some(verifier) => verifier.verify(password, hash),
+            None => Err(anyhow!("unsupported password hash format")),
+        }
+    }
+}
+
+impl PasswordHasher for ChainedHasher {
+    fn hash(&self, password: &str) -> Result<String> {
+        self.primary.hash(password)
+    }
+
+    fn needs_rehash(&self, hash: &str) -> bool {
+        !self.primary.supports(hash) || self.primary.needs_rehash(hash)
+    }
+}
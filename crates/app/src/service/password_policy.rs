@@ -0,0 +1,151 @@
+use std::{borrow::Cow, collections::HashSet, fs, path::Path, sync::Arc};
+
+use anyhow::{Result, anyhow};
+use validator::ValidationError;
+
+/// 内置的常见弱密码，比较时不区分大小写
+const COMMON_PASSWORDS: &[&str] = &[
+    "123456",
+    "12345678",
+    "123456789",
+    "1234567890",
+    "11111111",
+    "00000000",
+    "88888888",
+    "12341234",
+    "password",
+    "password1",
+    "password123",
+    "passw0rd",
+    "p@ssw0rd",
+    "p@ssword",
+    "qwerty",
+    "qwerty123",
+    "qwertyuiop",
+    "1q2w3e4r",
+    "1qaz2wsx",
+    "1qaz@wsx",
+    "abc123",
+    "abcd1234",
+    "a1234567",
+    "aa123456",
+    "admin",
+    "admin123",
+    "admin@123",
+    "administrator",
+    "iloveyou",
+    "letmein",
+    "welcome",
+    "welcome1",
+    "zxcvbnm",
+    "asdfghjkl",
+    "changeme",
+];
+
+/// 密码强度规则，注册、创建用户、修改密码时校验
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    /// 至少包含的字符类别数：小写字母、大写字母、数字、其他符号
+    pub min_classes: usize,
+    /// 禁止使用的密码，统一存为小写
+    pub denylist: Arc<HashSet<String>>,
+    /// 不能与最近几次设置过的密码相同，为 0 时不检查
+    pub history: usize,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 128,
+            min_classes: 3,
+            denylist: Arc::new(COMMON_PASSWORDS.iter().map(|p| p.to_string()).collect()),
+            history: 5,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// 在内置列表之外追加禁止使用的密码
+    pub fn with_denylist(mut self, passwords: impl IntoIterator<Item = String>) -> Self {
+        Arc::make_mut(&mut self.denylist)
+            .extend(passwords.into_iter().map(|p| p.trim().to_lowercase()));
+        self
+    }
+
+    /// 从文件追加禁止使用的密码，每行一个，忽略空行和 `#` 开头的行
+    pub fn with_denylist_file(self, path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow!("read password denylist {} error: {}", path.display(), e))?;
+        let passwords = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        Ok(self.with_denylist(passwords))
+    }
+
+    /// 检查长度、字符类别和禁用列表，是否与用户名相同由 [`not_username`] 检查
+    pub fn check(&self, password: &str) -> Result<(), ValidationError> {
+        let length = password.chars().count();
+        if length < self.min_length || length > self.max_length {
+            return Err(error(
+                "password_length",
+                format!(
+                    "password must be {} to {} characters",
+                    self.min_length, self.max_length
+                ),
+            ));
+        }
+
+        let classes = [
+            password.chars().any(|c| c.is_lowercase()),
+            password.chars().any(|c| c.is_uppercase()),
+            password.chars().any(|c| c.is_ascii_digit()),
+            password.chars().any(|c| !c.is_alphanumeric()),
+        ]
+        .into_iter()
+        .filter(|&present| present)
+        .count();
+        if classes < self.min_classes {
+            return Err(error(
+                "password_classes",
+                format!(
+                    "password must contain at least {} of lowercase, uppercase, digit and symbol",
+                    self.min_classes
+                ),
+            ));
+        }
+
+        if self.denylist.contains(&password.to_lowercase()) {
+            return Err(error(
+                "password_common",
+                "password is too common".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 供 `#[validate(custom(function = "validate_password", use_context))]` 使用
+pub fn validate_password(password: &str, policy: &PasswordPolicy) -> Result<(), ValidationError> {
+    policy.check(password)
+}
+
+/// 密码不能与用户名相同（不区分大小写）
+pub fn not_username(username: &str, password: &str) -> Result<(), ValidationError> {
+    if username.to_lowercase() == password.to_lowercase() {
+        return Err(error(
+            "password_username",
+            "password must not equal the username".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn error(code: &'static str, message: String) -> ValidationError {
+    ValidationError::new(code).with_message(Cow::Owned(message))
+}
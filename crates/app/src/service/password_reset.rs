@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use sea_orm::{
+    ActiveValue::{NotSet, Set},
+    ColumnTrait, Condition, ConnectionTrait, EntityTrait, QueryFilter,
+    sea_query::Expr,
+};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use uuid::Uuid;
+
+use crate::entity::{PasswordResetActiveModel, PasswordResetColumn, PasswordResetEntity};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasswordResetConfig {
+    /// 令牌有效期（秒）
+    pub ttl: u64,
+    /// 邮件中的重置链接，`{token}` 会替换为令牌；为空时邮件中只给出令牌
+    pub link: Option<String>,
+}
+
+impl Default for PasswordResetConfig {
+    fn default() -> Self {
+        Self {
+            ttl: 30 * 60,
+            link: None,
+        }
+    }
+}
+
+/// 数据库只保存令牌的摘要，泄露的数据不能直接用来重置密码
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha3_256::digest(token.as_bytes()))
+}
+
+/// 为用户签发新的重置令牌并作废之前未使用的令牌，返回令牌明文
+pub async fn create<C: ConnectionTrait>(
+    db: &C,
+    config: &PasswordResetConfig,
+    user_id: i64,
+) -> Result<String> {
+    PasswordResetEntity::delete_many()
+        .filter(PasswordResetColumn::UserId.eq(user_id))
+        .filter(PasswordResetColumn::UsedAt.is_null())
+        .exec(db)
+        .await
+        .context("delete password reset error")?;
+
+    let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let now = Utc::now();
+    PasswordResetEntity::insert(PasswordResetActiveModel {
+        id: NotSet,
+        user_id: Set(user_id),
+        token_hash: Set(hash_token(&token)),
+        created_at: Set(now),
+        expires_at: Set(now + Duration::from_secs(config.ttl)),
+        used_at: Set(None),
+    })
+    .exec(db)
+    .await
+    .context("create password reset error")?;
+    Ok(token)
+}
+
+/// 使用令牌，返回令牌所属的用户；令牌不存在、已过期或已使用时返回 `None`
+///
+/// 用条件更新标记已使用，并发请求中只有一个能成功
+pub async fn consume<C: ConnectionTrait>(db: &C, token: &str) -> Result<Option<i64>> {
+    let token_hash = hash_token(token);
+    let now = Utc::now();
+    let result = PasswordResetEntity::update_many()
+        .col_expr(PasswordResetColumn::UsedAt, Expr::value(now))
+        .filter(PasswordResetColumn::TokenHash.eq(&token_hash))
+        .filter(PasswordResetColumn::UsedAt.is_null())
+        .filter(PasswordResetColumn::ExpiresAt.gt(now))
+        .exec(db)
+        .await
+        .context("consume password reset error")?;
+    if result.rows_affected == 0 {
+        return Ok(None);
+    }
+
+    let reset = PasswordResetEntity::find()
+        .filter(PasswordResetColumn::TokenHash.eq(token_hash))
+        .one(db)
+        .await
+        .context("get password reset error")?;
+    Ok(reset.map(|reset| reset.user_id))
+}
+
+/// 删除已过期或已使用的令牌，返回删除的数量
+pub async fn delete_expired<C: ConnectionTrait>(db: &C) -> Result<u64> {
+    PasswordResetEntity::delete_many()
+        .filter(
+            Condition::any()
+                .add(PasswordResetColumn::ExpiresAt.lte(Utc::now()))
+                .add(PasswordResetColumn::UsedAt.is_not_null()),
+        )
+        .exec(db)
+        .await
+        .map(|res| res.rows_affected)
+        .context("delete expired password reset error")
+}
@@ -0,0 +1,180 @@
+use std::{collections::HashSet, fmt, str::FromStr};
+
+use anyhow::{Context, Result, anyhow};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, EntityTrait, JoinType, PaginatorTrait, QueryFilter, QuerySelect,
+    Related, TryGetableMany,
+};
+use serde::{Deserialize, Serialize};
+
+use super::role::STATUS_NORMAL;
+use crate::entity::{
+    MenuColumn, MenuEntity, RoleColumn, RoleEntity, RoleModel, UserRoleColumn, UserRoleEntity,
+};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PermissionConfig {
+    /// 兼容旧版本：权限码不匹配时，再按菜单路径前缀匹配请求路径
+    pub prefix_match: bool,
+    /// 判断超级管理员角色的规则
+    pub superuser: SuperuserRule,
+}
+
+/// 超级管理员角色的判断规则，只有状态正常的角色参与判断
+///
+/// 命令行和环境变量中的写法：`flag`、`data_scope:<n>`、`none`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuperuserRule {
+    /// `role.is_superuser` 为真
+    #[default]
+    Flag,
+    /// `role.data_scope` 等于给定值
+    DataScope(i16),
+    /// 不设超级管理员，所有权限都按权限码授予
+    None,
+}
+
+impl FromStr for SuperuserRule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once(':') {
+            None if s == "flag" => Ok(Self::Flag),
+            None if s == "none" => Ok(Self::None),
+            Some(("data_scope", value)) => value
+                .parse()
+                .map(Self::DataScope)
+                .map_err(|e| anyhow!("invalid data_scope `{}`: {}", value, e)),
+            _ => Err(anyhow!(
+                "invalid superuser rule `{}`, expected `flag`, `data_scope:<n>` or `none`",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SuperuserRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Flag => write!(f, "flag"),
+            Self::DataScope(value) => write!(f, "data_scope:{}", value),
+            Self::None => write!(f, "none"),
+        }
+    }
+}
+
+/// 一个会话拥有的权限
+#[derive(Debug, Clone, Default)]
+pub struct Permissions {
+    pub is_admin: bool,
+    /// 菜单上配置的权限码，例如 `user:list`
+    pub codes: HashSet<String>,
+    /// 菜单路径，仅在开启 `prefix_match` 时使用
+    pub paths: Vec<String>,
+}
+
+impl Permissions {
+    /// 权限码按完全相等匹配
+    pub fn allows(&self, config: &PermissionConfig, code: &str, uri: &str) -> bool {
+        self.is_admin
+            || self.codes.contains(code)
+            || (config.prefix_match && self.paths.iter().any(|path| uri.starts_with(path)))
+    }
+}
+
+/// 角色是否按 `rule` 判定为超级管理员角色；不看状态，停用的角色之后可能重新启用
+pub fn grants_superuser(rule: &SuperuserRule, role: &RoleModel) -> bool {
+    match rule {
+        SuperuserRule::Flag => role.is_superuser,
+        SuperuserRule::DataScope(value) => role.data_scope == *value,
+        SuperuserRule::None => false,
+    }
+}
+
+/// 用户是否拥有按 `rule` 判定为超级管理员的正常状态角色
+pub async fn is_superuser<C: ConnectionTrait>(
+    db: &C,
+    rule: &SuperuserRule,
+    user_id: i64,
+) -> Result<bool> {
+    let condition = match rule {
+        SuperuserRule::Flag => RoleColumn::IsSuperuser.eq(true),
+        SuperuserRule::DataScope(value) => RoleColumn::DataScope.eq(*value),
+        SuperuserRule::None => return Ok(false),
+    };
+
+    RoleEntity::find()
+        .inner_join(UserRoleEntity)
+        .filter(UserRoleColumn::UserId.eq(user_id))
+        .filter(RoleColumn::Status.eq(STATUS_NORMAL))
+        .filter(RoleColumn::DeletedAt.is_null())
+        .filter(condition)
+        .count(db)
+        .await
+        .map(|count| count > 0)
+        .context("check superuser error")
+}
+
+/// 加载用户的权限，超级管理员不再加载权限码
+pub async fn load<C: ConnectionTrait>(
+    db: &C,
+    config: &PermissionConfig,
+    user_id: i64,
+) -> Result<Permissions> {
+    if is_superuser(db, &config.superuser, user_id).await? {
+        return Ok(Permissions {
+            is_admin: true,
+            ..Default::default()
+        });
+    }
+
+    let codes = menu_column::<_, String>(db, MenuColumn::Perms, user_id)
+        .await?
+        .into_iter()
+        .filter(|code| !code.is_empty())
+        .collect();
+    let paths = if config.prefix_match {
+        menu_column(db, MenuColumn::Path, user_id).await?
+    } else {
+        Vec::new()
+    };
+
+    Ok(Permissions {
+        is_admin: false,
+        codes,
+        paths,
+    })
+}
+
+/// 用户通过正常状态角色获得的菜单 id，不考虑超级管理员
+pub async fn menu_ids<C: ConnectionTrait>(db: &C, user_id: i64) -> Result<HashSet<i32>> {
+    menu_column(db, MenuColumn::Id, user_id)
+        .await
+        .map(|ids| ids.into_iter().collect())
+}
+
+/// 查询用户通过正常状态角色获得的菜单的某一列
+async fn menu_column<C: ConnectionTrait, T: TryGetableMany>(
+    db: &C,
+    column: MenuColumn,
+    user_id: i64,
+) -> Result<Vec<T>> {
+    MenuEntity::find()
+        .select_only()
+        .column(column)
+        .distinct()
+        .inner_join(RoleEntity)
+        .join(
+            JoinType::InnerJoin,
+            <RoleEntity as Related<UserRoleEntity>>::to(),
+        )
+        .filter(UserRoleColumn::UserId.eq(user_id))
+        .filter(RoleColumn::Status.eq(STATUS_NORMAL))
+        .filter(RoleColumn::DeletedAt.is_null())
+        .filter(MenuColumn::DeletedAt.is_null())
+        .into_tuple()
+        .all(db)
+        .await
+        .context("load permissions error")
+}
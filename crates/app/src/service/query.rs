@@ -0,0 +1,242 @@
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, DbErr, Order, PaginatorTrait, SelectorTrait,
+    sea_query::LikeExpr,
+};
+use serde::{Deserialize, Serialize};
+use utils::{bytes_to_hex, bytes_to_u64, hex_to_bytes, u64_to_bytes};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use super::error::{InvalidCursor, InvalidSort};
+
+/// 排序方向
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl From<SortOrder> for Order {
+    fn from(order: SortOrder) -> Self {
+        match order {
+            SortOrder::Asc => Order::Asc,
+            SortOrder::Desc => Order::Desc,
+        }
+    }
+}
+
+/// 列表接口共用的查询条件，`filter` 为各列表自己的过滤字段
+#[derive(Debug, Clone, Deserialize, ToSchema, Validate)]
+pub struct ListQuery<F> {
+    #[validate(range(min = 1, message = "page must be greater than 0"))]
+    pub page: u64,
+    #[validate(range(
+        min = 1,
+        max = 100,
+        message = "page_size must be greater than 0 and less than 100"
+    ))]
+    pub page_size: u64,
+    /// 在各列表约定的文本字段中模糊搜索
+    #[serde(default)]
+    #[validate(length(max = 100))]
+    pub keyword: Option<String>,
+    #[serde(default)]
+    pub filter: F,
+    /// 排序字段，只能是各列表允许的字段，未指定时按默认字段排序
+    #[serde(default)]
+    pub sort: Option<String>,
+    #[serde(default)]
+    pub order: SortOrder,
+    /// 按游标分页：传空字符串从头开始，之后传上一页返回的 `next_cursor`；
+    /// 此时忽略 `page`，按各列表固定的键排序，不返回 `total`
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+impl<F: Default> ListQuery<F> {
+    pub fn new(page: u64, page_size: u64) -> Self {
+        Self {
+            page,
+            page_size,
+            keyword: None,
+            filter: F::default(),
+            sort: None,
+            order: SortOrder::default(),
+            cursor: None,
+        }
+    }
+}
+
+/// 分页方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Paging {
+    /// 按 `page` 跳过前面的行
+    Offset,
+    /// 从上一页最后一行之后开始，`None` 为第一页
+    Cursor(Option<Cursor>),
+}
+
+/// 游标分页的位置，即上一页最后一行的整数主键或时间戳；对外编码为不透明的十六进制字符串
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor {
+    pub key: u64,
+}
+
+impl Cursor {
+    pub fn new(key: u64) -> Self {
+        Self { key }
+    }
+
+    /// 按主键的类型取出 `key`，超出范围的游标不可能由列表接口返回
+    pub fn id<T: TryFrom<u64>>(&self) -> Result<T, InvalidCursor> {
+        T::try_from(self.key).map_err(|_| InvalidCursor {
+            reason: "cursor out of range",
+        })
+    }
+
+    pub fn encode(&self) -> String {
+        bytes_to_hex(&u64_to_bytes(self.key))
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, InvalidCursor> {
+        let invalid = || InvalidCursor {
+            reason: "malformed cursor",
+        };
+        let bytes = hex_to_bytes(cursor).map_err(|_| invalid())?;
+        if bytes.len() != 8 {
+            return Err(invalid());
+        }
+        Ok(Self::new(bytes_to_u64(&bytes).map_err(|_| invalid())?))
+    }
+}
+
+impl<F> ListQuery<F> {
+    /// 游标分页按固定的键排序，不能同时指定 `sort`
+    pub fn paging(&self) -> Result<Paging, InvalidCursor> {
+        match self.cursor.as_deref() {
+            None => Ok(Paging::Offset),
+            Some(_) if self.sort.is_some() => Err(InvalidCursor {
+                reason: "sort is not supported with cursor paging",
+            }),
+            Some("") => Ok(Paging::Cursor(None)),
+            Some(cursor) => Cursor::decode(cursor).map(|cursor| Paging::Cursor(Some(cursor))),
+        }
+    }
+
+    /// 按白名单把 `sort` 解析为列，未指定时使用 `default`
+    pub fn sort_column<Col: Copy>(
+        &self,
+        fields: &[(&'static str, Col)],
+        default: Col,
+    ) -> Result<Col, InvalidSort> {
+        let Some(sort) = &self.sort else {
+            return Ok(default);
+        };
+        fields
+            .iter()
+            .find(|(name, _)| name == sort)
+            .map(|(_, column)| *column)
+            .ok_or_else(|| InvalidSort {
+                field: sort.clone(),
+                allowed: fields.iter().map(|(name, _)| *name).collect(),
+            })
+    }
+
+    /// 任一列包含关键字即匹配，没有关键字时不限制；`%` 和 `_` 按字面匹配
+    pub fn keyword_condition<Col: ColumnTrait>(&self, columns: &[Col]) -> Condition {
+        let Some(keyword) = self
+            .keyword
+            .as_deref()
+            .map(str::trim)
+            .filter(|k| !k.is_empty())
+        else {
+            return Condition::all();
+        };
+        let escaped = keyword
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        columns.iter().fold(Condition::any(), |condition, column| {
+            condition.add(column.like(LikeExpr::new(format!("%{}%", escaped)).escape('\\')))
+        })
+    }
+}
+
+/// 一页数据，按页码分页时带有满足条件的总数，按游标分页时带有下一页的游标
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: Option<u64>,
+    pub page: u64,
+    pub page_size: u64,
+    /// 为 `None` 时已经是最后一页
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            page: self.page,
+            page_size: self.page_size,
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
+/// 统计总数并取出 `query` 指定的一页，调用方负责过滤和排序；
+/// 未经校验的 `page` 和 `page_size` 为 0 时按 1 处理
+pub async fn fetch_page<'db, C, S, F>(
+    db: &'db C,
+    select: S,
+    query: &ListQuery<F>,
+) -> Result<Page<<S::Selector as SelectorTrait>::Item>, DbErr>
+where
+    C: ConnectionTrait,
+    S: PaginatorTrait<'db, C>,
+{
+    let page = query.page.max(1);
+    let page_size = query.page_size.max(1);
+    let paginator = select.paginate(db, page_size);
+    let total = paginator.num_items().await?;
+    let items = paginator.fetch_page(page - 1).await?;
+    Ok(Page {
+        items,
+        total: Some(total),
+        page,
+        page_size,
+        next_cursor: None,
+    })
+}
+
+/// 取出游标之后的一页，调用方负责按游标过滤并按游标的键排序，`cursor` 返回一行对应的游标；
+/// 多取一行来判断是否还有下一页
+pub async fn fetch_after<'db, C, S, F>(
+    db: &'db C,
+    select: S,
+    query: &ListQuery<F>,
+    cursor: impl Fn(&<S::Selector as SelectorTrait>::Item) -> Cursor,
+) -> Result<Page<<S::Selector as SelectorTrait>::Item>, DbErr>
+where
+    C: ConnectionTrait,
+    S: PaginatorTrait<'db, C>,
+{
+    let page_size = query.page_size.max(1);
+    let mut items = select.paginate(db, page_size + 1).fetch_page(0).await?;
+    let next_cursor = if items.len() as u64 > page_size {
+        items.truncate(page_size as usize);
+        items.last().map(|item| cursor(item).encode())
+    } else {
+        None
+    };
+    Ok(Page {
+        items,
+        total: None,
+        page: 1,
+        page_size,
+        next_cursor,
+    })
+}
@@ -1,69 +1,246 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use sea_orm::{
     ActiveValue::{NotSet, Set},
-    ConnectionTrait, EntityTrait, QueryOrder, QuerySelect,
+    ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect, QueryTrait,
+    TransactionTrait,
+    sea_query::Expr,
 };
+use serde::Deserialize;
+use utoipa::ToSchema;
 
-use crate::entity::{RoleActiveModel, RoleColumn, RoleEntity, RoleModel};
+use super::{
+    deletion::{self, Dependents},
+    online,
+    query::{Cursor, ListQuery, Page, Paging, fetch_after, fetch_page},
+    user,
+};
+use crate::entity::{
+    RoleActiveModel, RoleColumn, RoleDeptColumn, RoleDeptEntity, RoleEntity, RoleMenuColumn,
+    RoleMenuEntity, RoleModel, UserRoleColumn, UserRoleEntity,
+};
+
+/// 角色状态：正常
+pub const STATUS_NORMAL: i16 = 0;
+/// 角色状态：停用，停用的角色不授予任何权限
+pub const STATUS_DISABLED: i16 = 1;
 
 pub async fn create<C: ConnectionTrait>(
     db: &C,
     name: &str,
     data_scope: i16,
     status: i16,
+    is_superuser: bool,
 ) -> Result<RoleModel> {
     RoleEntity::insert(RoleActiveModel {
         id: NotSet,
         name: Set(name.to_string()),
         data_scope: Set(data_scope),
         status: Set(status),
+        is_superuser: Set(is_superuser),
+        deleted_at: NotSet,
     })
     .exec_with_returning(db)
     .await
-    .map_err(|e| anyhow::anyhow!("create role error: {}", e))
+    .context("create role error")
 }
 
-pub async fn delete<C: ConnectionTrait>(db: &C, id: i32) -> Result<()> {
-    RoleEntity::delete_by_id(id)
+/// 软删除角色并使拥有该角色的用户已签发的访问令牌失效；
+/// 用户、菜单和部门关联按 `dependents` 处理，保留的关联在恢复后重新生效
+pub async fn delete<C>(db: &C, id: i32, dependents: Dependents) -> Result<()>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let txn = db.begin().await?;
+    user::bump_perm_version(&txn, user::with_role(id)).await?;
+    unlink(&txn, id, dependents, false).await?;
+    RoleEntity::update_many()
+        .col_expr(RoleColumn::DeletedAt, Expr::value(Utc::now()))
+        .filter(RoleColumn::Id.eq(id))
+        .filter(RoleColumn::DeletedAt.is_null())
+        .exec(&txn)
+        .await
+        .context("delete role error")?;
+    txn.commit().await?;
+    Ok(())
+}
+
+/// 恢复软删除的角色，拥有该角色的用户已签发的访问令牌随即失效；
+/// 返回这些用户，缓存的权限里没有已删除的角色，只能按用户使缓存失效
+pub async fn restore<C: ConnectionTrait>(db: &C, id: i32) -> Result<Vec<i64>> {
+    RoleEntity::update_many()
+        .col_expr(
+            RoleColumn::DeletedAt,
+            Expr::value(Option::<DateTime<Utc>>::None),
+        )
+        .filter(RoleColumn::Id.eq(id))
+        .filter(RoleColumn::DeletedAt.is_not_null())
         .exec(db)
         .await
-        .map(|_| ())
-        .map_err(|e| anyhow::anyhow!("delete role error: {}", e))
+        .context("restore role error")?;
+    user::bump_perm_version(db, user::with_role(id)).await?;
+    UserRoleEntity::find()
+        .select_only()
+        .column(UserRoleColumn::UserId)
+        .filter(UserRoleColumn::RoleId.eq(id))
+        .into_tuple()
+        .all(db)
+        .await
+        .context("restore role error")
 }
 
+/// 彻底删除角色，关联按 `dependents` 处理，`Cascade` 和 `Detach` 都会删除关联
+pub async fn purge<C>(db: &C, id: i32, dependents: Dependents) -> Result<()>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let txn = db.begin().await?;
+    user::bump_perm_version(&txn, user::with_role(id)).await?;
+    unlink(&txn, id, dependents, true).await?;
+    RoleEntity::delete_by_id(id)
+        .exec(&txn)
+        .await
+        .context("purge role error")?;
+    txn.commit().await?;
+    Ok(())
+}
+
+async fn unlink<C: ConnectionTrait>(
+    db: &C,
+    id: i32,
+    dependents: Dependents,
+    purge: bool,
+) -> Result<()> {
+    let owner = ("role", i64::from(id));
+    let role_id = i64::from(id);
+    deletion::unlink::<UserRoleEntity, _>(
+        db,
+        owner,
+        UserRoleColumn::RoleId.eq(role_id),
+        dependents,
+        purge,
+    )
+    .await?;
+    deletion::unlink::<RoleMenuEntity, _>(
+        db,
+        owner,
+        RoleMenuColumn::RoleId.eq(role_id),
+        dependents,
+        purge,
+    )
+    .await?;
+    deletion::unlink::<RoleDeptEntity, _>(
+        db,
+        owner,
+        RoleDeptColumn::RoleId.eq(role_id),
+        dependents,
+        purge,
+    )
+    .await
+}
+
+/// 停用角色时同时注销拥有该角色的用户的所有在线会话；
+/// 修改名称以外的属性会使这些用户已签发的访问令牌失效；
+/// 不存在或已删除的角色返回 [`DbErr::RecordNotUpdated`](sea_orm::DbErr::RecordNotUpdated)
 pub async fn update<C: ConnectionTrait>(
     db: &C,
     id: i32,
     name: Option<String>,
     data_scope: Option<i16>,
     status: Option<i16>,
+    is_superuser: Option<bool>,
 ) -> Result<()> {
+    let revoke = data_scope.is_some() || status.is_some() || is_superuser.is_some();
     RoleEntity::update(RoleActiveModel {
         id: Set(id),
         name: name.map(Set).unwrap_or(NotSet),
         data_scope: data_scope.map(Set).unwrap_or(NotSet),
         status: status.map(Set).unwrap_or(NotSet),
+        is_superuser: is_superuser.map(Set).unwrap_or(NotSet),
+        deleted_at: NotSet,
     })
+    .filter(RoleColumn::DeletedAt.is_null())
     .exec(db)
     .await
-    .map(|_| ())
-    .map_err(|e| anyhow::anyhow!("update role error: {}", e))
+    .context("update role error")?;
+
+    if revoke {
+        user::bump_perm_version(db, user::with_role(id)).await?;
+    }
+    if status == Some(STATUS_DISABLED) {
+        let count = online::delete_by_role(db, id).await?;
+        tracing::info!("role {} disabled, {} sessions revoked", id, count);
+    }
+    Ok(())
 }
 
 pub async fn get<C: ConnectionTrait>(db: &C, id: i32) -> Result<Option<RoleModel>> {
     RoleEntity::find_by_id(id)
+        .filter(RoleColumn::DeletedAt.is_null())
         .one(db)
         .await
-        .map_err(|e| anyhow::anyhow!("get role error: {}", e))
+        .context("get role error")
 }
 
-pub async fn list<C: ConnectionTrait>(db: &C, page: u64, page_size: u64) -> Result<Vec<RoleModel>> {
-    let offset = (page - 1) * page_size;
-    RoleEntity::find()
-        .order_by_asc(RoleColumn::Id)
-        .offset(offset)
-        .limit(page_size)
-        .all(db)
+/// 已软删除的角色
+pub async fn get_deleted<C: ConnectionTrait>(db: &C, id: i32) -> Result<Option<RoleModel>> {
+    RoleEntity::find_by_id(id)
+        .filter(RoleColumn::DeletedAt.is_not_null())
+        .one(db)
         .await
-        .map_err(|e| anyhow::anyhow!("list role error: {}", e))
+        .context("get role error")
+}
+
+/// 角色列表的过滤条件
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct RoleFilter {
+    pub status: Option<i16>,
+    pub is_superuser: Option<bool>,
+    /// 只列出已软删除的角色
+    #[serde(default)]
+    pub deleted: bool,
+}
+
+/// 角色列表允许的排序字段
+const SORT_FIELDS: [(&str, RoleColumn); 3] = [
+    ("id", RoleColumn::Id),
+    ("name", RoleColumn::Name),
+    ("status", RoleColumn::Status),
+];
+
+/// 关键字匹配角色名称
+pub async fn list<C: ConnectionTrait>(
+    db: &C,
+    query: &ListQuery<RoleFilter>,
+) -> Result<Page<RoleModel>> {
+    let filter = &query.filter;
+    let select = RoleEntity::find()
+        .filter(deletion::deleted_condition(
+            RoleColumn::DeletedAt,
+            filter.deleted,
+        ))
+        .filter(query.keyword_condition(&[RoleColumn::Name]))
+        .apply_if(filter.status, |select, status| {
+            select.filter(RoleColumn::Status.eq(status))
+        })
+        .apply_if(filter.is_superuser, |select, is_superuser| {
+            select.filter(RoleColumn::IsSuperuser.eq(is_superuser))
+        });
+    let page = match query.paging()? {
+        Paging::Offset => {
+            let sort = query.sort_column(&SORT_FIELDS, RoleColumn::Id)?;
+            let select = select
+                .order_by(sort, query.order.into())
+                .order_by_asc(RoleColumn::Id);
+            fetch_page(db, select, query).await
+        }
+        Paging::Cursor(after) => {
+            let after = after.map(|after| after.id::<i32>()).transpose()?;
+            let select = select
+                .apply_if(after, |select, id| select.filter(RoleColumn::Id.gt(id)))
+                .order_by_asc(RoleColumn::Id);
+            fetch_after(db, select, query, |role| Cursor::new(role.id as u64)).await
+        }
+    };
+    page.context("list role error")
 }
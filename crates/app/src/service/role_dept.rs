@@ -0,0 +1,82 @@
+use anyhow::{Context, Result};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder,
+    TransactionTrait,
+};
+
+use super::{error::MissingReferences, user};
+use crate::entity::{
+    DeptColumn, DeptEntity, DeptModel, RoleColumn, RoleDeptActiveModel, RoleDeptColumn,
+    RoleDeptEntity, RoleEntity,
+};
+
+pub async fn list<C: ConnectionTrait>(db: &C, role_id: i32) -> Result<Vec<DeptModel>> {
+    DeptEntity::find()
+        .inner_join(RoleDeptEntity)
+        .filter(RoleDeptColumn::RoleId.eq(i64::from(role_id)))
+        .order_by_asc(DeptColumn::Id)
+        .all(db)
+        .await
+        .context("list role dept error")
+}
+
+/// 在事务中用 `dept_ids` 整体替换角色自定数据权限的部门，返回替换后的部门
+pub async fn assign<C>(db: &C, role_id: i32, dept_ids: &[i64]) -> Result<Vec<DeptModel>>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut dept_ids = dept_ids.to_vec();
+    dept_ids.sort_unstable();
+    dept_ids.dedup();
+
+    let txn = db.begin().await?;
+
+    if RoleEntity::find_by_id(role_id)
+        .filter(RoleColumn::DeletedAt.is_null())
+        .one(&txn)
+        .await?
+        .is_none()
+    {
+        return Err(MissingReferences {
+            entity: "role",
+            ids: vec![i64::from(role_id)],
+        }
+        .into());
+    }
+
+    let depts = DeptEntity::find()
+        .filter(DeptColumn::Id.is_in(dept_ids.clone()))
+        .all(&txn)
+        .await?;
+    if depts.len() != dept_ids.len() {
+        let ids = dept_ids
+            .iter()
+            .filter(|id| !depts.iter().any(|dept| dept.id == **id))
+            .copied()
+            .collect();
+        return Err(MissingReferences {
+            entity: "dept",
+            ids,
+        }
+        .into());
+    }
+
+    RoleDeptEntity::delete_many()
+        .filter(RoleDeptColumn::RoleId.eq(i64::from(role_id)))
+        .exec(&txn)
+        .await?;
+
+    if !dept_ids.is_empty() {
+        RoleDeptEntity::insert_many(dept_ids.iter().map(|dept_id| RoleDeptActiveModel {
+            role_id: Set(i64::from(role_id)),
+            dept_id: Set(*dept_id),
+        }))
+        .exec(&txn)
+        .await?;
+    }
+
+    user::bump_perm_version(&txn, user::with_role(role_id)).await?;
+    let depts = list(&txn, role_id).await?;
+    txn.commit().await?;
+    Ok(depts)
+}
@@ -0,0 +1,84 @@
+use anyhow::{Context, Result};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder,
+    TransactionTrait,
+};
+
+use super::{error::MissingReferences, user};
+use crate::entity::{
+    MenuColumn, MenuEntity, MenuModel, RoleColumn, RoleEntity, RoleMenuActiveModel, RoleMenuColumn,
+    RoleMenuEntity,
+};
+
+pub async fn list<C: ConnectionTrait>(db: &C, role_id: i32) -> Result<Vec<MenuModel>> {
+    MenuEntity::find()
+        .inner_join(RoleMenuEntity)
+        .filter(RoleMenuColumn::RoleId.eq(i64::from(role_id)))
+        .filter(MenuColumn::DeletedAt.is_null())
+        .order_by_asc(MenuColumn::Id)
+        .all(db)
+        .await
+        .context("list role menu error")
+}
+
+/// 在事务中用 `menu_ids` 整体替换角色的菜单，返回替换后的菜单
+pub async fn assign<C>(db: &C, role_id: i32, menu_ids: &[i32]) -> Result<Vec<MenuModel>>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut menu_ids = menu_ids.to_vec();
+    menu_ids.sort_unstable();
+    menu_ids.dedup();
+
+    let txn = db.begin().await?;
+
+    if RoleEntity::find_by_id(role_id)
+        .filter(RoleColumn::DeletedAt.is_null())
+        .one(&txn)
+        .await?
+        .is_none()
+    {
+        return Err(MissingReferences {
+            entity: "role",
+            ids: vec![i64::from(role_id)],
+        }
+        .into());
+    }
+
+    let menus = MenuEntity::find()
+        .filter(MenuColumn::Id.is_in(menu_ids.clone()))
+        .filter(MenuColumn::DeletedAt.is_null())
+        .all(&txn)
+        .await?;
+    if menus.len() != menu_ids.len() {
+        let ids = menu_ids
+            .iter()
+            .filter(|id| !menus.iter().any(|menu| menu.id == **id))
+            .map(|id| i64::from(*id))
+            .collect();
+        return Err(MissingReferences {
+            entity: "menu",
+            ids,
+        }
+        .into());
+    }
+
+    RoleMenuEntity::delete_many()
+        .filter(RoleMenuColumn::RoleId.eq(i64::from(role_id)))
+        .exec(&txn)
+        .await?;
+
+    if !menu_ids.is_empty() {
+        RoleMenuEntity::insert_many(menu_ids.iter().map(|menu_id| RoleMenuActiveModel {
+            role_id: Set(i64::from(role_id)),
+            menu_id: Set(i64::from(*menu_id)),
+        }))
+        .exec(&txn)
+        .await?;
+    }
+
+    user::bump_perm_version(&txn, user::with_role(role_id)).await?;
+    let menus = list(&txn, role_id).await?;
+    txn.commit().await?;
+    Ok(menus)
+}
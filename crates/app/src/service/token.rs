@@ -0,0 +1,212 @@
+use std::{collections::HashMap, fs, path::Path, str::FromStr};
+
+use anyhow::{Result, anyhow, bail};
+use chrono::Utc;
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, decode_header, encode,
+    errors::ErrorKind,
+};
+use serde::{Deserialize, Serialize};
+
+/// HS256 密钥的最短长度（字节）
+const MIN_SECRET_LEN: usize = 32;
+
+/// 访问令牌的载荷
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    /// 用户 id
+    pub sub: String,
+    /// 签发时用户的权限版本，与 `user.perm_version` 不一致时令牌失效
+    pub pv: i32,
+    /// 同时签发的刷新令牌所属的 family，注销时据此删除刷新令牌
+    pub sid: String,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+impl Claims {
+    pub fn user_id(&self) -> Result<i64> {
+        self.sub
+            .parse()
+            .map_err(|e| anyhow!("invalid token subject `{}`: {}", self.sub, e))
+    }
+}
+
+pub enum Access {
+    Valid(Claims),
+    Expired,
+    Invalid,
+}
+
+/// 密钥文件（TOML）中的一个密钥，按 `kid` 区分
+///
+/// 轮换密钥时加入新密钥并把 `active` 指向它，旧密钥保留到其签发的令牌全部过期后再删除；
+/// 只用于校验的 Ed25519 密钥可以省略 `private_key`
+#[derive(Deserialize)]
+#[serde(tag = "algorithm")]
+enum KeyEntry {
+    HS256 {
+        kid: String,
+        secret: String,
+    },
+    EdDSA {
+        kid: String,
+        private_key: Option<String>,
+        public_key: String,
+    },
+}
+
+#[derive(Deserialize)]
+struct KeyFile {
+    /// 签发新令牌使用的密钥
+    active: String,
+    keys: Vec<KeyEntry>,
+}
+
+/// 签发和校验访问令牌的密钥集合
+pub struct KeySet {
+    active: String,
+    algorithm: Algorithm,
+    encoding: EncodingKey,
+    decoding: HashMap<String, (Algorithm, DecodingKey)>,
+}
+
+impl KeySet {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow!("read key file {} error: {}", path.display(), e))?;
+        content.parse()
+    }
+}
+
+impl FromStr for KeySet {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let file: KeyFile =
+            toml::from_str(s).map_err(|e| anyhow!("parse key file error: {}", e))?;
+
+        let mut decoding = HashMap::new();
+        let mut active = None;
+        for entry in file.keys {
+            let (kid, algorithm, decoding_key, encoding_key) = match entry {
+                KeyEntry::HS256 { kid, secret } => {
+                    if secret.len() < MIN_SECRET_LEN {
+                        bail!(
+                            "HS256 key `{}` must be at least {} bytes",
+                            kid,
+                            MIN_SECRET_LEN
+                        );
+                    }
+                    let secret = secret.as_bytes();
+                    (
+                        kid,
+                        Algorithm::HS256,
+                        DecodingKey::from_secret(secret),
+                        Some(EncodingKey::from_secret(secret)),
+                    )
+                }
+                KeyEntry::EdDSA {
+                    kid,
+                    private_key,
+                    public_key,
+                } => {
+                    let decoding_key = DecodingKey::from_ed_pem(public_key.as_bytes())
+                        .map_err(|e| anyhow!("invalid public key `{}`: {}", kid, e))?;
+                    let encoding_key = private_key
+                        .map(|pem| EncodingKey::from_ed_pem(pem.as_bytes()))
+                        .transpose()
+                        .map_err(|e| anyhow!("invalid private key `{}`: {}", kid, e))?;
+                    (kid, Algorithm::EdDSA, decoding_key, encoding_key)
+                }
+            };
+
+            if kid == file.active {
+                active = Some((algorithm, encoding_key));
+            }
+            if decoding
+                .insert(kid.clone(), (algorithm, decoding_key))
+                .is_some()
+            {
+                bail!("duplicate key id `{}`", kid);
+            }
+        }
+
+        match active {
+            Some((algorithm, Some(encoding))) => Ok(Self {
+                active: file.active,
+                algorithm,
+                encoding,
+                decoding,
+            }),
+            Some((_, None)) => bail!("active key `{}` has no private key", file.active),
+            None => bail!("active key `{}` not found", file.active),
+        }
+    }
+}
+
+/// 无状态访问令牌的配置，刷新令牌的有效期沿用 [`SessionConfig`](super::online::SessionConfig)
+pub struct JwtConfig {
+    pub keys: KeySet,
+    /// 访问令牌有效期（秒）
+    pub access_ttl: u64,
+}
+
+impl JwtConfig {
+    pub fn new(keys: KeySet) -> Self {
+        Self {
+            keys,
+            access_ttl: 15 * 60,
+        }
+    }
+
+    pub fn with_access_ttl(mut self, access_ttl: u64) -> Self {
+        self.access_ttl = access_ttl;
+        self
+    }
+
+    /// 用当前密钥签发访问令牌，头部带上 `kid`
+    pub fn issue(&self, user_id: i64, perm_version: i32, family: &str) -> Result<String> {
+        let now = Utc::now().timestamp();
+        let claims = Claims {
+            sub: user_id.to_string(),
+            pv: perm_version,
+            sid: family.to_string(),
+            iat: now,
+            exp: now + self.access_ttl as i64,
+        };
+        let header = Header {
+            kid: Some(self.keys.active.clone()),
+            ..Header::new(self.keys.algorithm)
+        };
+        encode(&header, &claims, &self.keys.encoding)
+            .map_err(|e| anyhow!("issue access token error: {}", e))
+    }
+
+    /// 按头部的 `kid` 选择密钥校验签名和有效期，未知的 `kid` 视为无效
+    pub fn verify(&self, token: &str) -> Access {
+        let Some((algorithm, key)) = decode_header(token)
+            .ok()
+            .and_then(|header| header.kid)
+            .and_then(|kid| self.keys.decoding.get(&kid))
+        else {
+            return Access::Invalid;
+        };
+
+        let mut validation = Validation::new(*algorithm);
+        validation.leeway = 0;
+        match decode::<Claims>(token, key, &validation) {
+            Ok(data) => Access::Valid(data.claims),
+            Err(e) if matches!(e.kind(), ErrorKind::ExpiredSignature) => Access::Expired,
+            Err(e) => {
+                tracing::debug!("invalid access token: {}", e);
+                Access::Invalid
+            }
+        }
+    }
+}
+
+/// 访问令牌是点分隔的三段，会话令牌不含 `.`
+pub fn is_jwt(token: &str) -> bool {
+    token.contains('.')
+}
@@ -1,63 +1,475 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use sea_orm::{
     ActiveValue::{NotSet, Set},
-    ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+    ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+    QueryTrait, TransactionTrait,
+    sea_query::{Expr, IntoCondition, Query},
 };
+use serde::Deserialize;
+use utoipa::ToSchema;
 
-use crate::entity::{UserActiveModel, UserColumn, UserEntity, UserModel};
+use super::{
+    data_scope::DataScope,
+    deletion::{self, Dependents},
+    error::{Conflict, PasswordReused},
+    online,
+    password::PasswordHasher,
+    query::{Cursor, ListQuery, Page, Paging, fetch_after, fetch_page},
+};
+use crate::entity::{
+    PasswordHistoryActiveModel, PasswordHistoryColumn, PasswordHistoryEntity, RoleMenuColumn,
+    RoleMenuEntity, UserActiveModel, UserColumn, UserEntity, UserModel, UserRoleColumn,
+    UserRoleEntity,
+};
+
+/// 用户状态：正常
+pub const STATUS_ACTIVE: i16 = 0;
+/// 用户状态：停用
+pub const STATUS_DISABLED: i16 = 1;
+/// 用户状态：锁定
+pub const STATUS_LOCKED: i16 = 2;
+/// 用户状态：待激活
+pub const STATUS_PENDING: i16 = 3;
+
+/// 用户资料，修改时为 `None` 的字段保持不变
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    /// 找回密码使用的邮箱，不能与其他用户重复
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub nickname: Option<String>,
+    /// 头像地址
+    pub avatar: Option<String>,
+}
+
+pub async fn create<C: ConnectionTrait>(
+    db: &C,
+    hasher: &dyn PasswordHasher,
+    name: &str,
+    password: &str,
+) -> Result<UserModel> {
+    create_with_profile(db, hasher, name, password, Profile::default()).await
+}
 
-pub async fn create<C: ConnectionTrait>(db: &C, name: &str, password: &str) -> Result<UserModel> {
-    UserEntity::insert(UserActiveModel {
+/// 用户名或邮箱已存在时返回 [`Conflict`]
+pub async fn create_with_profile<C: ConnectionTrait>(
+    db: &C,
+    hasher: &dyn PasswordHasher,
+    name: &str,
+    password: &str,
+    profile: Profile,
+) -> Result<UserModel> {
+    let now = Utc::now();
+    let user = UserEntity::insert(UserActiveModel {
         id: NotSet,
         name: Set(name.to_string()),
-        password: Set(password.to_string()),
+        password: Set(hasher.hash(password)?),
+        status: Set(STATUS_ACTIVE),
+        dept_id: NotSet,
+        perm_version: Set(0),
+        email: Set(profile.email),
+        phone: Set(profile.phone),
+        nickname: Set(profile.nickname),
+        avatar: Set(profile.avatar),
+        created_at: Set(now),
+        updated_at: Set(now),
+        last_login_at: NotSet,
+        deleted_at: NotSet,
     })
     .exec_with_returning(db)
     .await
-    .map_err(|e| anyhow::anyhow!("create user error: {}", e))
+    .map_err(|e| write_error("create user", e))?;
+
+    record_password(db, user.id, &user.password).await?;
+    Ok(user)
 }
 
-pub async fn delete<C: ConnectionTrait>(db: &C, id: i64) -> Result<()> {
-    UserEntity::delete_by_id(id)
+/// 软删除用户，注销该用户的所有在线会话并使已签发的访问令牌失效；
+/// 用户的角色按 `dependents` 处理，保留的角色在恢复后重新生效
+pub async fn delete<C>(db: &C, id: i64, dependents: Dependents) -> Result<()>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let txn = db.begin().await?;
+    unlink_roles(&txn, id, dependents, false).await?;
+
+    let now = Utc::now();
+    UserEntity::update_many()
+        .col_expr(UserColumn::DeletedAt, Expr::value(now))
+        .col_expr(UserColumn::UpdatedAt, Expr::value(now))
+        .filter(UserColumn::Id.eq(id))
+        .filter(UserColumn::DeletedAt.is_null())
+        .exec(&txn)
+        .await
+        .context("delete user error")?;
+    bump_perm_version(&txn, UserColumn::Id.eq(id)).await?;
+    let count = online::delete_by_user(&txn, id).await?;
+    txn.commit().await?;
+
+    tracing::info!("user {} deleted, {} sessions revoked", id, count);
+    Ok(())
+}
+
+/// 恢复软删除的用户，用户名和邮箱在删除期间仍然占用，恢复不会产生冲突
+pub async fn restore<C: ConnectionTrait>(db: &C, id: i64) -> Result<()> {
+    UserEntity::update_many()
+        .col_expr(
+            UserColumn::DeletedAt,
+            Expr::value(Option::<DateTime<Utc>>::None),
+        )
+        .col_expr(UserColumn::UpdatedAt, Expr::value(Utc::now()))
+        .filter(UserColumn::Id.eq(id))
+        .filter(UserColumn::DeletedAt.is_not_null())
         .exec(db)
         .await
         .map(|_| ())
-        .map_err(|e| anyhow::anyhow!("delete user error: {}", e))
+        .context("restore user error")
+}
+
+/// 彻底删除用户，角色按 `dependents` 处理，`Cascade` 和 `Detach` 都会删除角色关联
+pub async fn purge<C>(db: &C, id: i64, dependents: Dependents) -> Result<()>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let txn = db.begin().await?;
+    unlink_roles(&txn, id, dependents, true).await?;
+    online::delete_by_user(&txn, id).await?;
+    UserEntity::delete_by_id(id)
+        .exec(&txn)
+        .await
+        .context("purge user error")?;
+    txn.commit().await?;
+    Ok(())
 }
 
+async fn unlink_roles<C: ConnectionTrait>(
+    db: &C,
+    id: i64,
+    dependents: Dependents,
+    purge: bool,
+) -> Result<()> {
+    deletion::unlink::<UserRoleEntity, _>(
+        db,
+        ("user", id),
+        UserRoleColumn::UserId.eq(id),
+        dependents,
+        purge,
+    )
+    .await
+}
+
+/// 状态改为非正常时同时注销该用户的所有在线会话；修改状态会使已签发的访问令牌失效，
+/// 修改密码见 [`change_password`]，用户名已存在时返回 [`Conflict`]
 pub async fn update<C: ConnectionTrait>(
     db: &C,
     id: i64,
     name: Option<String>,
-    password: Option<String>,
+    status: Option<i16>,
 ) -> Result<()> {
     UserEntity::update(UserActiveModel {
         id: Set(id),
         name: name.map(Set).unwrap_or(NotSet),
-        password: password.map(Set).unwrap_or(NotSet),
+        password: NotSet,
+        status: status.map(Set).unwrap_or(NotSet),
+        dept_id: NotSet,
+        perm_version: NotSet,
+        email: NotSet,
+        phone: NotSet,
+        nickname: NotSet,
+        avatar: NotSet,
+        created_at: NotSet,
+        updated_at: Set(Utc::now()),
+        last_login_at: NotSet,
+        deleted_at: NotSet,
+    })
+    .exec(db)
+    .await
+    .map_err(|e| write_error("update user", e))?;
+
+    if status.is_some() {
+        bump_perm_version(db, UserColumn::Id.eq(id)).await?;
+    }
+
+    if status.is_some_and(|status| status != STATUS_ACTIVE) {
+        let count = online::delete_by_user(db, id).await?;
+        tracing::info!("user {} deactivated, {} sessions revoked", id, count);
+    }
+    Ok(())
+}
+
+/// 修改密码并使已签发的访问令牌失效，会话由调用方决定是否注销
+///
+/// 新密码不能与当前密码及最近 `history` 条历史密码相同，否则返回 [`PasswordReused`]；
+/// 历史记录只保留最近 `history` 条
+pub async fn change_password<C: ConnectionTrait>(
+    db: &C,
+    hasher: &dyn PasswordHasher,
+    user: &UserModel,
+    password: &str,
+    history: usize,
+) -> Result<()> {
+    if history > 0 {
+        let previous = PasswordHistoryEntity::find()
+            .filter(PasswordHistoryColumn::UserId.eq(user.id))
+            .order_by_desc(PasswordHistoryColumn::Id)
+            .limit(history as u64)
+            .all(db)
+            .await
+            .context("list password history error")?;
+        for hash in std::iter::once(&user.password).chain(previous.iter().map(|p| &p.password)) {
+            if hasher.verify(password, hash)? {
+                return Err(PasswordReused { depth: history }.into());
+            }
+        }
+    }
+
+    let hash = hasher.hash(password)?;
+    UserEntity::update(UserActiveModel {
+        id: Set(user.id),
+        password: Set(hash.clone()),
+        updated_at: Set(Utc::now()),
+        ..Default::default()
+    })
+    .exec(db)
+    .await
+    .context("change password error")?;
+    bump_perm_version(db, UserColumn::Id.eq(user.id)).await?;
+
+    record_password(db, user.id, &hash).await?;
+    // 每次修改都会裁剪，记录数不会超过 `history + 1`
+    let ids: Vec<i64> = PasswordHistoryEntity::find()
+        .select_only()
+        .column(PasswordHistoryColumn::Id)
+        .filter(PasswordHistoryColumn::UserId.eq(user.id))
+        .order_by_desc(PasswordHistoryColumn::Id)
+        .into_tuple()
+        .all(db)
+        .await
+        .context("list password history error")?;
+    let stale: Vec<i64> = ids.into_iter().skip(history).collect();
+    if !stale.is_empty() {
+        PasswordHistoryEntity::delete_many()
+            .filter(PasswordHistoryColumn::Id.is_in(stale))
+            .exec(db)
+            .await
+            .context("prune password history error")?;
+    }
+    Ok(())
+}
+
+async fn record_password<C: ConnectionTrait>(db: &C, user_id: i64, hash: &str) -> Result<()> {
+    PasswordHistoryEntity::insert(PasswordHistoryActiveModel {
+        id: NotSet,
+        user_id: Set(user_id),
+        password: Set(hash.to_string()),
+        created_at: Set(Utc::now()),
+    })
+    .exec(db)
+    .await
+    .map(|_| ())
+    .context("record password history error")
+}
+
+/// 设置用户所属部门，`None` 表示不属于任何部门
+pub async fn set_dept<C: ConnectionTrait>(db: &C, id: i64, dept_id: Option<i64>) -> Result<()> {
+    UserEntity::update(UserActiveModel {
+        id: Set(id),
+        dept_id: Set(dept_id),
+        updated_at: Set(Utc::now()),
+        ..Default::default()
+    })
+    .exec(db)
+    .await
+    .context("set user dept error")?;
+
+    bump_perm_version(db, UserColumn::Id.eq(id))
+        .await
+        .map(|_| ())
+}
+
+/// 修改用户资料，邮箱已被其他用户使用时返回 [`Conflict`]
+pub async fn set_profile<C: ConnectionTrait>(db: &C, id: i64, profile: Profile) -> Result<()> {
+    let set = |value: Option<String>| value.map(|v| Set(Some(v))).unwrap_or(NotSet);
+    UserEntity::update(UserActiveModel {
+        id: Set(id),
+        email: set(profile.email),
+        phone: set(profile.phone),
+        nickname: set(profile.nickname),
+        avatar: set(profile.avatar),
+        updated_at: Set(Utc::now()),
+        ..Default::default()
+    })
+    .exec(db)
+    .await
+    .map(|_| ())
+    .map_err(|e| write_error("set user profile", e))
+}
+
+/// 记录登录成功的时间，不影响 `updated_at`
+pub async fn record_login<C: ConnectionTrait>(db: &C, id: i64) -> Result<()> {
+    UserEntity::update(UserActiveModel {
+        id: Set(id),
+        last_login_at: Set(Some(Utc::now())),
+        ..Default::default()
     })
     .exec(db)
     .await
     .map(|_| ())
-    .map_err(|e| anyhow::anyhow!("update user error: {}", e))
+    .context("record user login error")
+}
+
+/// 唯一约束冲突转为 [`Conflict`]，其他错误保留原始信息
+fn write_error(action: &str, e: DbErr) -> anyhow::Error {
+    match Conflict::from_db(&e, "user", &["email", "name"]) {
+        Some(conflict) => conflict.into(),
+        None => anyhow::Error::from(e).context(format!("{} error", action)),
+    }
+}
+
+/// 递增满足条件的用户的权限版本，携带旧版本的访问令牌随即失效，需要用刷新令牌换新
+pub async fn bump_perm_version<C: ConnectionTrait>(
+    db: &C,
+    condition: impl IntoCondition,
+) -> Result<u64> {
+    UserEntity::update_many()
+        .col_expr(
+            UserColumn::PermVersion,
+            Expr::col(UserColumn::PermVersion).add(1),
+        )
+        .filter(condition)
+        .exec(db)
+        .await
+        .map(|res| res.rows_affected)
+        .context("bump permission version error")
 }
 
-pub async fn get<C: ConnectionTrait>(db: &C, id: i64) -> Result<Option<UserModel>> {
-    UserEntity::find_by_id(id)
+/// 拥有该角色的用户
+pub fn with_role(role_id: i32) -> impl IntoCondition {
+    UserColumn::Id.in_subquery(
+        Query::select()
+            .column(UserRoleColumn::UserId)
+            .from(UserRoleEntity)
+            .and_where(UserRoleColumn::RoleId.eq(role_id))
+            .to_owned(),
+    )
+}
+
+/// 通过角色获得该菜单的用户
+pub fn with_menu(menu_id: i32) -> impl IntoCondition {
+    UserColumn::Id.in_subquery(
+        Query::select()
+            .column(UserRoleColumn::UserId)
+            .from(UserRoleEntity)
+            .inner_join(
+                RoleMenuEntity,
+                Expr::col((RoleMenuEntity, RoleMenuColumn::RoleId))
+                    .equals((UserRoleEntity, UserRoleColumn::RoleId)),
+            )
+            .and_where(Expr::col((RoleMenuEntity, RoleMenuColumn::MenuId)).eq(menu_id))
+            .to_owned(),
+    )
+}
+
+/// 只返回 `scope` 范围内的用户
+pub async fn get<C: ConnectionTrait>(
+    db: &C,
+    scope: &DataScope,
+    id: i64,
+) -> Result<Option<UserModel>> {
+    scope
+        .apply(UserEntity::find_by_id(id))
+        .filter(UserColumn::DeletedAt.is_null())
         .one(db)
         .await
-        .map_err(|e| anyhow::anyhow!("get user error: {}", e))
+        .context("get user error")
 }
 
-pub async fn list<C: ConnectionTrait>(db: &C, page: u64, page_size: u64) -> Result<Vec<UserModel>> {
-    let offset = (page - 1) * page_size;
-    UserEntity::find()
-        .order_by_asc(UserColumn::Id)
-        .offset(offset)
-        .limit(page_size)
-        .all(db)
+/// 只返回 `scope` 范围内已软删除的用户
+pub async fn get_deleted<C: ConnectionTrait>(
+    db: &C,
+    scope: &DataScope,
+    id: i64,
+) -> Result<Option<UserModel>> {
+    scope
+        .apply(UserEntity::find_by_id(id))
+        .filter(UserColumn::DeletedAt.is_not_null())
+        .one(db)
         .await
-        .map_err(|e| anyhow::anyhow!("list user error: {}", e))
+        .context("get user error")
+}
+
+/// 用户列表的过滤条件
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UserFilter {
+    pub status: Option<i16>,
+    pub dept_id: Option<i64>,
+    /// 创建时间不早于该时间
+    pub created_from: Option<DateTime<Utc>>,
+    /// 创建时间早于该时间
+    pub created_to: Option<DateTime<Utc>>,
+    /// 只列出已软删除的用户
+    #[serde(default)]
+    pub deleted: bool,
+}
+
+/// 用户列表允许的排序字段
+const SORT_FIELDS: [(&str, UserColumn); 5] = [
+    ("id", UserColumn::Id),
+    ("username", UserColumn::Name),
+    ("created_at", UserColumn::CreatedAt),
+    ("updated_at", UserColumn::UpdatedAt),
+    ("last_login_at", UserColumn::LastLoginAt),
+];
+
+/// 只返回 `scope` 范围内的用户，关键字匹配用户名、昵称、邮箱和手机号
+pub async fn list<C: ConnectionTrait>(
+    db: &C,
+    scope: &DataScope,
+    query: &ListQuery<UserFilter>,
+) -> Result<Page<UserModel>> {
+    let filter = &query.filter;
+    let select = scope
+        .apply(UserEntity::find())
+        .filter(deletion::deleted_condition(
+            UserColumn::DeletedAt,
+            filter.deleted,
+        ))
+        .filter(query.keyword_condition(&[
+            UserColumn::Name,
+            UserColumn::Nickname,
+            UserColumn::Email,
+            UserColumn::Phone,
+        ]))
+        .apply_if(filter.status, |select, status| {
+            select.filter(UserColumn::Status.eq(status))
+        })
+        .apply_if(filter.dept_id, |select, dept_id| {
+            select.filter(UserColumn::DeptId.eq(dept_id))
+        })
+        .apply_if(filter.created_from, |select, from| {
+            select.filter(UserColumn::CreatedAt.gte(from))
+        })
+        .apply_if(filter.created_to, |select, to| {
+            select.filter(UserColumn::CreatedAt.lt(to))
+        });
+    let page = match query.paging()? {
+        Paging::Offset => {
+            let sort = query.sort_column(&SORT_FIELDS, UserColumn::Id)?;
+            let select = select
+                .order_by(sort, query.order.into())
+                .order_by_asc(UserColumn::Id);
+            fetch_page(db, select, query).await
+        }
+        Paging::Cursor(after) => {
+            let after = after.map(|after| after.id::<i64>()).transpose()?;
+            let select = select
+                .apply_if(after, |select, id| select.filter(UserColumn::Id.gt(id)))
+                .order_by_asc(UserColumn::Id);
+            fetch_after(db, select, query, |user| Cursor::new(user.id as u64)).await
+        }
+    };
+    page.context("list user error")
 }
 
 pub async fn get_by_username<C: ConnectionTrait>(
@@ -66,7 +478,43 @@ pub async fn get_by_username<C: ConnectionTrait>(
 ) -> Result<Option<UserModel>> {
     let user = UserEntity::find()
         .filter(UserColumn::Name.eq(username))
+        .filter(UserColumn::DeletedAt.is_null())
+        .one(db)
+        .await?;
+    Ok(user)
+}
+
+pub async fn get_by_email<C: ConnectionTrait>(db: &C, email: &str) -> Result<Option<UserModel>> {
+    let user = UserEntity::find()
+        .filter(UserColumn::Email.eq(email))
+        .filter(UserColumn::DeletedAt.is_null())
         .one(db)
         .await?;
     Ok(user)
 }
+
+/// 校验密码，校验通过且存储的哈希已过时（明文、旧算法或旧参数）时顺带重新计算并保存
+pub async fn verify_password<C: ConnectionTrait>(
+    db: &C,
+    hasher: &dyn PasswordHasher,
+    user: &UserModel,
+    password: &str,
+) -> Result<bool> {
+    if !hasher.verify(password, &user.password)? {
+        return Ok(false);
+    }
+
+    // 密码没有变化，不需要像 `update` 那样使已签发的令牌失效
+    if hasher.needs_rehash(&user.password) {
+        tracing::info!("rehash password for user {}", user.id);
+        UserEntity::update(UserActiveModel {
+            id: Set(user.id),
+            password: Set(hasher.hash(password)?),
+            ..Default::default()
+        })
+        .exec(db)
+        .await
+        .context("rehash password error")?;
+    }
+    Ok(true)
+}
@@ -0,0 +1,94 @@
+use anyhow::{Context, Result};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder,
+    TransactionTrait, sea_query::Query,
+};
+
+use super::{error::MissingReferences, user};
+use crate::entity::{
+    RoleColumn, RoleEntity, RoleModel, UserColumn, UserEntity, UserRoleActiveModel, UserRoleColumn,
+    UserRoleEntity,
+};
+
+pub async fn list<C: ConnectionTrait>(db: &C, user_id: i64) -> Result<Vec<RoleModel>> {
+    RoleEntity::find()
+        .inner_join(UserRoleEntity)
+        .filter(UserRoleColumn::UserId.eq(user_id))
+        .filter(RoleColumn::DeletedAt.is_null())
+        .order_by_asc(RoleColumn::Id)
+        .all(db)
+        .await
+        .context("list user role error")
+}
+
+/// 在事务中用 `role_ids` 整体替换用户的角色，返回替换后的角色
+pub async fn assign<C>(db: &C, user_id: i64, role_ids: &[i32]) -> Result<Vec<RoleModel>>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut role_ids = role_ids.to_vec();
+    role_ids.sort_unstable();
+    role_ids.dedup();
+
+    let txn = db.begin().await?;
+
+    if UserEntity::find_by_id(user_id)
+        .filter(UserColumn::DeletedAt.is_null())
+        .one(&txn)
+        .await?
+        .is_none()
+    {
+        return Err(MissingReferences {
+            entity: "user",
+            ids: vec![user_id],
+        }
+        .into());
+    }
+
+    let roles = RoleEntity::find()
+        .filter(RoleColumn::Id.is_in(role_ids.clone()))
+        .filter(RoleColumn::DeletedAt.is_null())
+        .all(&txn)
+        .await?;
+    if roles.len() != role_ids.len() {
+        let ids = role_ids
+            .iter()
+            .filter(|id| !roles.iter().any(|role| role.id == **id))
+            .map(|id| i64::from(*id))
+            .collect();
+        return Err(MissingReferences {
+            entity: "role",
+            ids,
+        }
+        .into());
+    }
+
+    // 已删除角色的关联保留下来，恢复角色时用户仍然拥有它
+    UserRoleEntity::delete_many()
+        .filter(UserRoleColumn::UserId.eq(user_id))
+        .filter(
+            UserRoleColumn::RoleId.in_subquery(
+                Query::select()
+                    .column(RoleColumn::Id)
+                    .from(RoleEntity)
+                    .and_where(RoleColumn::DeletedAt.is_null())
+                    .to_owned(),
+            ),
+        )
+        .exec(&txn)
+        .await?;
+
+    if !role_ids.is_empty() {
+        UserRoleEntity::insert_many(role_ids.iter().map(|role_id| UserRoleActiveModel {
+            user_id: Set(user_id),
+            role_id: Set(i64::from(*role_id)),
+        }))
+        .exec(&txn)
+        .await?;
+    }
+
+    user::bump_perm_version(&txn, UserColumn::Id.eq(user_id)).await?;
+    let roles = list(&txn, user_id).await?;
+    txn.commit().await?;
+    Ok(roles)
+}
@@ -1,7 +1,8 @@
-use std::time::Instant;
+use std::{sync::Arc, time::Instant};
 
 use axum::{
     body::{Body, to_bytes},
+    extract::FromRef,
     http::{Request, Response},
     middleware::Next,
     response::IntoResponse,
@@ -10,7 +11,20 @@ use sea_orm::ConnectionTrait;
 use utoipa::OpenApi;
 use uuid::Uuid;
 
-use crate::controller::{AUTH_TAG, MENU_TAG, ROLE_TAG, USER_TAG};
+use crate::{
+    controller::{AUDIT_TAG, AUTH_TAG, MENU_TAG, ONLINE_TAG, ROLE_TAG, RPC_TAG, USER_TAG},
+    service::{
+        cache::PermissionCache,
+        login_guard::LoginGuard,
+        notifier::{LogNotifier, Notifier},
+        online::SessionConfig,
+        password::{ChainedHasher, PasswordHasher},
+        password_policy::PasswordPolicy,
+        password_reset::PasswordResetConfig,
+        permission::PermissionConfig,
+        token::JwtConfig,
+    },
+};
 
 #[derive(OpenApi)]
 #[openapi(
@@ -19,6 +33,9 @@ use crate::controller::{AUTH_TAG, MENU_TAG, ROLE_TAG, USER_TAG};
          (name = USER_TAG, description = "User API endpoints"),
          (name = ROLE_TAG, description = "Role API endpoints"),
          (name = MENU_TAG, description = "Menu API endpoints"),
+         (name = ONLINE_TAG, description = "Online session API endpoints"),
+         (name = RPC_TAG, description = "JSON-RPC 2.0 batch endpoint"),
+         (name = AUDIT_TAG, description = "Audit log API endpoints"),
     ),
 )]
 pub struct ApiDoc;
@@ -28,6 +45,17 @@ where
     C: ConnectionTrait,
 {
     pub db: C,
+    pub hasher: Box<dyn PasswordHasher>,
+    pub password_policy: PasswordPolicy,
+    pub password_reset: PasswordResetConfig,
+    /// 投递找回密码等通知，默认只写日志；在后台任务中发送，因此需要共享
+    pub notifier: Arc<dyn Notifier>,
+    pub session: SessionConfig,
+    pub permission: PermissionConfig,
+    pub permission_cache: PermissionCache,
+    pub login_guard: LoginGuard,
+    /// 配置后登录签发无状态的访问令牌和刷新令牌，否则只使用数据库会话
+    pub jwt: Option<JwtConfig>,
 }
 
 impl<C> WebState<C>
@@ -35,7 +63,73 @@ where
     C: ConnectionTrait,
 {
     pub fn new(db: C) -> Self {
-        Self { db }
+        Self {
+            db,
+            hasher: Box::new(ChainedHasher::default()),
+            password_policy: PasswordPolicy::default(),
+            password_reset: PasswordResetConfig::default(),
+            notifier: Arc::new(LogNotifier::default()),
+            session: SessionConfig::default(),
+            permission: PermissionConfig::default(),
+            permission_cache: PermissionCache::default(),
+            login_guard: LoginGuard::default(),
+            jwt: None,
+        }
+    }
+
+    pub fn with_hasher(mut self, hasher: Box<dyn PasswordHasher>) -> Self {
+        self.hasher = hasher;
+        self
+    }
+
+    pub fn with_password_policy(mut self, password_policy: PasswordPolicy) -> Self {
+        self.password_policy = password_policy;
+        self
+    }
+
+    pub fn with_password_reset_config(mut self, password_reset: PasswordResetConfig) -> Self {
+        self.password_reset = password_reset;
+        self
+    }
+
+    pub fn with_notifier(mut self, notifier: Box<dyn Notifier>) -> Self {
+        self.notifier = notifier.into();
+        self
+    }
+
+    pub fn with_session_config(mut self, session: SessionConfig) -> Self {
+        self.session = session;
+        self
+    }
+
+    pub fn with_permission_config(mut self, permission: PermissionConfig) -> Self {
+        self.permission = permission;
+        self
+    }
+
+    pub fn with_permission_cache(mut self, permission_cache: PermissionCache) -> Self {
+        self.permission_cache = permission_cache;
+        self
+    }
+
+    pub fn with_login_guard(mut self, login_guard: LoginGuard) -> Self {
+        self.login_guard = login_guard;
+        self
+    }
+
+    pub fn with_jwt_config(mut self, jwt: JwtConfig) -> Self {
+        self.jwt = Some(jwt);
+        self
+    }
+}
+
+/// 供 `axum_valid::ValidEx` 取得校验密码所需的策略
+impl<C> FromRef<Arc<WebState<C>>> for PasswordPolicy
+where
+    C: ConnectionTrait,
+{
+    fn from_ref(state: &Arc<WebState<C>>) -> Self {
+        state.password_policy.clone()
     }
 }
 
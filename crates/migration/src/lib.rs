@@ -0,0 +1,89 @@
+//! 数据库结构迁移
+//!
+//! [`Migrator`] 只包含表结构，[`SeedMigrator`] 写入初始数据，两者分别记录在不同的迁移表中，
+//! 因此初始数据可以按需执行或回滚，不影响表结构的迁移状态。
+//! 初始数据依赖完整的表结构，应通过 [`SeedMigrator::up_after_schema`] 在表结构迁移完成后写入。
+
+pub use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DatabaseConnection;
+
+mod m20261018_000001_create_dept;
+mod m20261018_000002_create_user;
+mod m20261018_000003_create_role;
+mod m20261018_000004_create_menu;
+mod m20261018_000005_create_online;
+mod m20261018_000006_create_user_role;
+mod m20261018_000007_create_role_menu;
+mod m20261018_000008_create_role_dept;
+mod m20261018_000009_seed;
+mod m20261018_000010_menu_tree;
+mod m20261018_000011_seed_menu_tree;
+mod m20261018_000012_token_rotation;
+mod m20261018_000013_password_history;
+mod m20261018_000014_password_reset;
+mod m20261018_000015_user_profile;
+mod m20261018_000016_soft_delete;
+mod m20261018_000017_audit_log;
+mod m20261018_000018_online_id;
+mod m20261018_000019_seed_user_timestamps;
+
+pub struct Migrator;
+
+#[async_trait::async_trait]
+impl MigratorTrait for Migrator {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        vec![
+            Box::new(m20261018_000001_create_dept::Migration),
+            Box::new(m20261018_000002_create_user::Migration),
+            Box::new(m20261018_000003_create_role::Migration),
+            Box::new(m20261018_000004_create_menu::Migration),
+            Box::new(m20261018_000005_create_online::Migration),
+            Box::new(m20261018_000006_create_user_role::Migration),
+            Box::new(m20261018_000007_create_role_menu::Migration),
+            Box::new(m20261018_000008_create_role_dept::Migration),
+            Box::new(m20261018_000010_menu_tree::Migration),
+            Box::new(m20261018_000012_token_rotation::Migration),
+            Box::new(m20261018_000013_password_history::Migration),
+            Box::new(m20261018_000014_password_reset::Migration),
+            Box::new(m20261018_000015_user_profile::Migration),
+            Box::new(m20261018_000016_soft_delete::Migration),
+            Box::new(m20261018_000017_audit_log::Migration),
+            Box::new(m20261018_000018_online_id::Migration),
+        ]
+    }
+}
+
+/// 初始数据：默认部门、admin 和 user 两个账号及其角色、全部菜单及其目录
+pub struct SeedMigrator;
+
+#[async_trait::async_trait]
+impl MigratorTrait for SeedMigrator {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        vec![
+            Box::new(m20261018_000009_seed::Migration),
+            Box::new(m20261018_000011_seed_menu_tree::Migration),
+            Box::new(m20261018_000019_seed_user_timestamps::Migration),
+        ]
+    }
+
+    fn migration_table_name() -> DynIden {
+        Alias::new("seaql_seed_migrations").into_iden()
+    }
+}
+
+impl SeedMigrator {
+    /// 写入初始数据；初始数据用到了后续表结构迁移加入的列（例如菜单层级和用户资料），
+    /// [`Migrator`] 还有未执行的迁移时直接返回错误，不写入任何数据
+    pub async fn up_after_schema(db: &DatabaseConnection, steps: Option<u32>) -> Result<(), DbErr> {
+        let pending = Migrator::get_pending_migrations(db).await?;
+        if !pending.is_empty() {
+            let names: Vec<_> = pending.iter().map(|m| m.name().to_string()).collect();
+            return Err(DbErr::Migration(format!(
+                "{} pending schema migrations: {:?}, run them before seeding",
+                pending.len(),
+                names
+            )));
+        }
+        Self::up(db, steps).await
+    }
+}
@@ -0,0 +1,34 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Dept::Table)
+                    .if_not_exists()
+                    .col(big_integer(Dept::Id).auto_increment().primary_key())
+                    .col(string_len(Dept::Name, 50))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Dept::Table).to_owned())
+            .await
+    }
+}
+
+/// 部门表
+#[derive(DeriveIden)]
+pub enum Dept {
+    Table,
+    Id,
+    Name,
+}
@@ -0,0 +1,48 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::m20261018_000001_create_dept::Dept;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(User::Table)
+                    .if_not_exists()
+                    .col(big_integer(User::Id).auto_increment().primary_key())
+                    .col(string_len_uniq(User::Name, 20))
+                    .col(string_len(User::Password, 255))
+                    .col(small_integer(User::Status).default(0))
+                    .col(big_integer_null(User::DeptId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_user_dept_id")
+                            .from(User::Table, User::DeptId)
+                            .to(Dept::Table, Dept::Id),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(User::Table).to_owned())
+            .await
+    }
+}
+
+/// 用户表，`password` 为 PHC 格式的哈希串，`status` 为 0正常 1停用 2锁定 3待激活
+#[derive(DeriveIden)]
+pub enum User {
+    Table,
+    Id,
+    Name,
+    Password,
+    Status,
+    DeptId,
+}
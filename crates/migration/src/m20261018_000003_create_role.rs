@@ -0,0 +1,40 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Role::Table)
+                    .if_not_exists()
+                    .col(pk_auto(Role::Id))
+                    .col(string_len(Role::Name, 20))
+                    .col(small_integer(Role::DataScope).default(1))
+                    .col(small_integer(Role::Status).default(0))
+                    .col(boolean(Role::IsSuperuser).default(false))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Role::Table).to_owned())
+            .await
+    }
+}
+
+/// 角色表，`data_scope` 为 0全部 1自定 2本部门 3仅本人，`status` 为 0正常 1停用
+#[derive(DeriveIden)]
+pub enum Role {
+    Table,
+    Id,
+    Name,
+    DataScope,
+    Status,
+    IsSuperuser,
+}
@@ -0,0 +1,40 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Menu::Table)
+                    .if_not_exists()
+                    .col(pk_auto(Menu::Id))
+                    .col(string_len(Menu::Name, 20))
+                    .col(string_len(Menu::Path, 100))
+                    .col(string_len(Menu::Perms, 100).default(""))
+                    .col(boolean(Menu::IsFrame).default(false))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Menu::Table).to_owned())
+            .await
+    }
+}
+
+/// 菜单表，`perms` 为权限码，例如 `user:list`
+#[derive(DeriveIden)]
+pub enum Menu {
+    Table,
+    Id,
+    Name,
+    Path,
+    Perms,
+    IsFrame,
+}
@@ -0,0 +1,64 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::m20261018_000002_create_user::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Online::Table)
+                    .if_not_exists()
+                    .col(string_len(Online::Token, 50).primary_key())
+                    .col(big_integer(Online::UserId))
+                    .col(
+                        timestamp_with_time_zone(Online::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        timestamp_with_time_zone(Online::LastSeenAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(timestamp_with_time_zone(Online::ExpiresAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_online_user_id")
+                            .from(Online::Table, Online::UserId)
+                            .to(User::Table, User::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("online_expires_at_idx")
+                    .table(Online::Table)
+                    .col(Online::ExpiresAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Online::Table).to_owned())
+            .await
+    }
+}
+
+/// 在线会话表
+#[derive(DeriveIden)]
+pub enum Online {
+    Table,
+    Token,
+    UserId,
+    CreatedAt,
+    LastSeenAt,
+    ExpiresAt,
+}
@@ -0,0 +1,49 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::{m20261018_000002_create_user::User, m20261018_000003_create_role::Role};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserRole::Table)
+                    .if_not_exists()
+                    .col(big_integer(UserRole::UserId))
+                    .col(big_integer(UserRole::RoleId))
+                    .primary_key(Index::create().col(UserRole::UserId).col(UserRole::RoleId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_user_role_user_id")
+                            .from(UserRole::Table, UserRole::UserId)
+                            .to(User::Table, User::Id),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_user_role_role_id")
+                            .from(UserRole::Table, UserRole::RoleId)
+                            .to(Role::Table, Role::Id),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserRole::Table).to_owned())
+            .await
+    }
+}
+
+/// 用户和角色关联表
+#[derive(DeriveIden)]
+pub enum UserRole {
+    Table,
+    UserId,
+    RoleId,
+}
@@ -0,0 +1,49 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::{m20261018_000003_create_role::Role, m20261018_000004_create_menu::Menu};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RoleMenu::Table)
+                    .if_not_exists()
+                    .col(big_integer(RoleMenu::RoleId))
+                    .col(big_integer(RoleMenu::MenuId))
+                    .primary_key(Index::create().col(RoleMenu::RoleId).col(RoleMenu::MenuId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_role_menu_role_id")
+                            .from(RoleMenu::Table, RoleMenu::RoleId)
+                            .to(Role::Table, Role::Id),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_role_menu_menu_id")
+                            .from(RoleMenu::Table, RoleMenu::MenuId)
+                            .to(Menu::Table, Menu::Id),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RoleMenu::Table).to_owned())
+            .await
+    }
+}
+
+/// 角色和菜单关联表
+#[derive(DeriveIden)]
+pub enum RoleMenu {
+    Table,
+    RoleId,
+    MenuId,
+}
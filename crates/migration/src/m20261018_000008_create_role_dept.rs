@@ -0,0 +1,49 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::{m20261018_000001_create_dept::Dept, m20261018_000003_create_role::Role};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RoleDept::Table)
+                    .if_not_exists()
+                    .col(big_integer(RoleDept::RoleId))
+                    .col(big_integer(RoleDept::DeptId))
+                    .primary_key(Index::create().col(RoleDept::RoleId).col(RoleDept::DeptId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_role_dept_role_id")
+                            .from(RoleDept::Table, RoleDept::RoleId)
+                            .to(Role::Table, Role::Id),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_role_dept_dept_id")
+                            .from(RoleDept::Table, RoleDept::DeptId)
+                            .to(Dept::Table, Dept::Id),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RoleDept::Table).to_owned())
+            .await
+    }
+}
+
+/// 角色和部门关联表，用于自定数据权限
+#[derive(DeriveIden)]
+pub enum RoleDept {
+    Table,
+    RoleId,
+    DeptId,
+}
@@ -0,0 +1,253 @@
+use sea_orm_migration::{prelude::*, sea_orm::DatabaseBackend};
+
+use super::{
+    m20261018_000001_create_dept::Dept, m20261018_000002_create_user::User,
+    m20261018_000003_create_role::Role, m20261018_000004_create_menu::Menu,
+    m20261018_000005_create_online::Online, m20261018_000006_create_user_role::UserRole,
+    m20261018_000007_create_role_menu::RoleMenu, m20261018_000008_create_role_dept::RoleDept,
+};
+
+/// 超级管理员角色
+const ADMIN_ROLE_ID: i32 = 1;
+/// 普通用户角色，拥有全部菜单和默认部门的数据
+const USER_ROLE_ID: i32 = 2;
+const DEFAULT_DEPT_ID: i64 = 1;
+
+/// 初始密码为明文，首次登录成功后自动重新哈希
+const USERS: &[(i64, &str, &str, i32)] = &[
+    (1, "admin", "admin123", ADMIN_ROLE_ID),
+    (2, "user", "user123", USER_ROLE_ID),
+];
+
+const MENUS: &[(i32, &str, &str, &str)] = &[
+    (1, "管理用户", "/user/list", "user:list"),
+    (2, "获取用户", "/user/get", "user:get"),
+    (3, "新增用户", "/user/create", "user:create"),
+    (4, "编辑用户", "/user/update", "user:update"),
+    (5, "删除用户", "/user/delete", "user:delete"),
+    (6, "管理角色", "/role/list", "role:list"),
+    (7, "获取角色", "/role/get", "role:get"),
+    (8, "新增角色", "/role/create", "role:create"),
+    (9, "编辑角色", "/role/update", "role:update"),
+    (10, "删除角色", "/role/delete", "role:delete"),
+    (11, "管理菜单", "/menu/list", "menu:list"),
+    (12, "获取菜单", "/menu/get", "menu:get"),
+    (13, "新增菜单", "/menu/create", "menu:create"),
+    (14, "编辑菜单", "/menu/update", "menu:update"),
+    (15, "删除菜单", "/menu/delete", "menu:delete"),
+    (16, "管理在线用户", "/online/list", "online:list"),
+    (17, "获取在线用户", "/online/get", "online:get"),
+    (18, "删除在线用户", "/online/delete", "online:delete"),
+    (19, "踢出用户", "/online/kick", "online:kick"),
+    (20, "获取用户角色", "/user/roles", "user:roles"),
+    (
+        21,
+        "分配用户角色",
+        "/user/assign_roles",
+        "user:assign_roles",
+    ),
+    (22, "获取角色菜单", "/role/menus", "role:menus"),
+    (
+        23,
+        "分配角色菜单",
+        "/role/assign_menus",
+        "role:assign_menus",
+    ),
+    (24, "获取角色部门", "/role/depts", "role:depts"),
+    (
+        25,
+        "分配角色部门",
+        "/role/assign_depts",
+        "role:assign_depts",
+    ),
+];
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(Dept::Table)
+                    .columns([Dept::Id, Dept::Name])
+                    .values_panic([DEFAULT_DEPT_ID.into(), "默认部门".into()])
+                    .to_owned(),
+            )
+            .await?;
+
+        let mut users = Query::insert();
+        users
+            .into_table(User::Table)
+            .columns([User::Id, User::Name, User::Password, User::DeptId]);
+        for (id, name, password, _) in USERS {
+            users.values_panic([
+                (*id).into(),
+                (*name).into(),
+                (*password).into(),
+                DEFAULT_DEPT_ID.into(),
+            ]);
+        }
+        manager.exec_stmt(users).await?;
+
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(Role::Table)
+                    .columns([Role::Id, Role::Name, Role::DataScope, Role::IsSuperuser])
+                    .values_panic([ADMIN_ROLE_ID.into(), "admin".into(), 0.into(), true.into()])
+                    .values_panic([USER_ROLE_ID.into(), "user".into(), 1.into(), false.into()])
+                    .to_owned(),
+            )
+            .await?;
+
+        let mut menus = Query::insert();
+        menus
+            .into_table(Menu::Table)
+            .columns([Menu::Id, Menu::Name, Menu::Path, Menu::Perms]);
+        for (id, name, path, perms) in MENUS {
+            menus.values_panic([
+                (*id).into(),
+                (*name).into(),
+                (*path).into(),
+                (*perms).into(),
+            ]);
+        }
+        manager.exec_stmt(menus).await?;
+
+        let mut user_roles = Query::insert();
+        user_roles
+            .into_table(UserRole::Table)
+            .columns([UserRole::UserId, UserRole::RoleId]);
+        for (id, _, _, role_id) in USERS {
+            user_roles.values_panic([(*id).into(), (*role_id).into()]);
+        }
+        manager.exec_stmt(user_roles).await?;
+
+        let mut role_menus = Query::insert();
+        role_menus
+            .into_table(RoleMenu::Table)
+            .columns([RoleMenu::RoleId, RoleMenu::MenuId]);
+        for (id, ..) in MENUS {
+            role_menus.values_panic([USER_ROLE_ID.into(), (*id).into()]);
+        }
+        manager.exec_stmt(role_menus).await?;
+
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(RoleDept::Table)
+                    .columns([RoleDept::RoleId, RoleDept::DeptId])
+                    .values_panic([USER_ROLE_ID.into(), DEFAULT_DEPT_ID.into()])
+                    .to_owned(),
+            )
+            .await?;
+
+        // 显式写入了 id，Postgres 需要把序列推进到已有的最大值
+        if manager.get_database_backend() == DatabaseBackend::Postgres {
+            for table in ["dept", "user", "role", "menu"] {
+                manager
+                    .get_connection()
+                    .execute_unprepared(&format!(
+                        r#"SELECT setval(pg_get_serial_sequence('"{table}"', 'id'), (SELECT MAX(id) FROM "{table}"))"#
+                    ))
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let user_ids = USERS.iter().map(|(id, ..)| *id);
+        let menu_ids = MENUS.iter().map(|(id, ..)| *id);
+        let role_ids = [ADMIN_ROLE_ID, USER_ROLE_ID];
+
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(RoleDept::Table)
+                    .cond_where(
+                        Condition::any()
+                            .add(Expr::col(RoleDept::RoleId).is_in(role_ids))
+                            .add(Expr::col(RoleDept::DeptId).eq(DEFAULT_DEPT_ID)),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(RoleMenu::Table)
+                    .cond_where(
+                        Condition::any()
+                            .add(Expr::col(RoleMenu::RoleId).is_in(role_ids))
+                            .add(Expr::col(RoleMenu::MenuId).is_in(menu_ids.clone())),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(UserRole::Table)
+                    .cond_where(
+                        Condition::any()
+                            .add(Expr::col(UserRole::UserId).is_in(user_ids.clone()))
+                            .add(Expr::col(UserRole::RoleId).is_in(role_ids)),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(Online::Table)
+                    .and_where(Expr::col(Online::UserId).is_in(user_ids.clone()))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(Menu::Table)
+                    .and_where(Expr::col(Menu::Id).is_in(menu_ids))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(Role::Table)
+                    .and_where(Expr::col(Role::Id).is_in(role_ids))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(User::Table)
+                    .and_where(Expr::col(User::Id).is_in(user_ids))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(User::Table)
+                    .value(User::DeptId, Option::<i64>::None)
+                    .and_where(Expr::col(User::DeptId).eq(DEFAULT_DEPT_ID))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(Dept::Table)
+                    .and_where(Expr::col(Dept::Id).eq(DEFAULT_DEPT_ID))
+                    .to_owned(),
+            )
+            .await
+    }
+}
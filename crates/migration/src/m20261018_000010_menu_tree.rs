@@ -0,0 +1,78 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::m20261018_000004_create_menu::Menu;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite 一条 ALTER TABLE 只能修改一列
+        for column in [
+            integer_null(MenuTree::ParentId),
+            integer(MenuTree::SortOrder).default(0).take(),
+            string_len(MenuTree::Icon, 100).default("").take(),
+            small_integer(MenuTree::MenuType).default(1).take(),
+            boolean(MenuTree::Visible).default(true).take(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Menu::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("menu_parent_id_idx")
+                    .table(Menu::Table)
+                    .col(MenuTree::ParentId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("menu_parent_id_idx")
+                    .table(Menu::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        for column in [
+            MenuTree::ParentId,
+            MenuTree::SortOrder,
+            MenuTree::Icon,
+            MenuTree::MenuType,
+            MenuTree::Visible,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Menu::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// 菜单的层级和展示属性，`menu_type` 为 0目录 1页面 2按钮
+#[derive(DeriveIden)]
+pub enum MenuTree {
+    ParentId,
+    SortOrder,
+    Icon,
+    MenuType,
+    Visible,
+}
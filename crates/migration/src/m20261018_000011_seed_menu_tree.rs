@@ -0,0 +1,118 @@
+use sea_orm_migration::{prelude::*, sea_orm::FromQueryResult};
+
+use super::{m20261018_000004_create_menu::Menu, m20261018_000010_menu_tree::MenuTree};
+
+const DIRECTORY: i16 = 0;
+const PAGE: i16 = 1;
+const BUTTON: i16 = 2;
+
+/// 目录名称、路径、图标，以及归入该目录的权限码前缀
+const DIRECTORIES: &[(&str, &str, &str, &str)] = &[
+    ("用户管理", "/user", "user", "user:"),
+    ("角色管理", "/role", "peoples", "role:"),
+    ("菜单管理", "/menu", "tree-table", "menu:"),
+    ("在线用户", "/online", "online", "online:"),
+];
+
+#[derive(FromQueryResult)]
+struct Id {
+    id: i32,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+
+        for (sort_order, (name, path, icon, prefix)) in DIRECTORIES.iter().enumerate() {
+            manager
+                .exec_stmt(
+                    Query::insert()
+                        .into_table(Menu::Table)
+                        .columns([
+                            Menu::Name.into_iden(),
+                            Menu::Path.into_iden(),
+                            MenuTree::Icon.into_iden(),
+                            MenuTree::MenuType.into_iden(),
+                            MenuTree::SortOrder.into_iden(),
+                        ])
+                        .values_panic([
+                            (*name).into(),
+                            (*path).into(),
+                            (*icon).into(),
+                            DIRECTORY.into(),
+                            (sort_order as i32).into(),
+                        ])
+                        .to_owned(),
+                )
+                .await?;
+
+            let Some(Id { id }) = Id::find_by_statement(
+                backend.build(
+                    Query::select()
+                        .column(Menu::Id)
+                        .from(Menu::Table)
+                        .and_where(Expr::col(Menu::Path).eq(*path))
+                        .and_where(Expr::col(MenuTree::MenuType).eq(DIRECTORY)),
+                ),
+            )
+            .one(db)
+            .await?
+            else {
+                return Err(DbErr::RecordNotFound(format!("menu directory {path}")));
+            };
+
+            // `xxx:list` 是页面，其余是按钮
+            manager
+                .exec_stmt(
+                    Query::update()
+                        .table(Menu::Table)
+                        .value(MenuTree::ParentId, id)
+                        .value(
+                            MenuTree::MenuType,
+                            Expr::case(Expr::col(Menu::Perms).eq(format!("{prefix}list")), PAGE)
+                                .finally(BUTTON),
+                        )
+                        .and_where(Expr::col(Menu::Perms).like(format!("{prefix}%")))
+                        .and_where(Expr::col(MenuTree::ParentId).is_null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let paths = DIRECTORIES.iter().map(|(_, path, ..)| *path);
+        let directories = Query::select()
+            .column(Menu::Id)
+            .from(Menu::Table)
+            .and_where(Expr::col(Menu::Path).is_in(paths.clone()))
+            .and_where(Expr::col(MenuTree::MenuType).eq(DIRECTORY))
+            .to_owned();
+
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(Menu::Table)
+                    .value(MenuTree::ParentId, Option::<i32>::None)
+                    .value(MenuTree::MenuType, PAGE)
+                    .and_where(Expr::col(MenuTree::ParentId).in_subquery(directories))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(Menu::Table)
+                    .and_where(Expr::col(Menu::Path).is_in(paths))
+                    .and_where(Expr::col(MenuTree::MenuType).eq(DIRECTORY))
+                    .to_owned(),
+            )
+            .await
+    }
+}
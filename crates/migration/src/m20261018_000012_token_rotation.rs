@@ -0,0 +1,86 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::{m20261018_000002_create_user::User, m20261018_000005_create_online::Online};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .add_column(integer(TokenRotation::PermVersion).default(0))
+                    .to_owned(),
+            )
+            .await?;
+
+        // SQLite 一条 ALTER TABLE 只能修改一列
+        for column in [
+            string_len_null(TokenRotation::Family, 50),
+            timestamp_with_time_zone_null(TokenRotation::RotatedAt),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Online::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("online_family_idx")
+                    .table(Online::Table)
+                    .col(TokenRotation::Family)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("online_family_idx")
+                    .table(Online::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        for column in [TokenRotation::Family, TokenRotation::RotatedAt] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Online::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .drop_column(TokenRotation::PermVersion)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// `user.perm_version` 在权限变化时递增，使已签发的访问令牌失效；
+/// `online.family` 不为空的记录是刷新令牌，同一次登录轮换出的刷新令牌属于同一个 family，
+/// `rotated_at` 记录被轮换的时间，再次使用即视为泄露
+#[derive(DeriveIden)]
+pub enum TokenRotation {
+    PermVersion,
+    Family,
+    RotatedAt,
+}
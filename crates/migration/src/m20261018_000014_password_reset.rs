@@ -0,0 +1,102 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::m20261018_000002_create_user::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .add_column(string_len_null(UserEmail::Email, 100))
+                    .to_owned(),
+            )
+            .await?;
+
+        // 唯一索引允许多个用户的邮箱为空
+        manager
+            .create_index(
+                Index::create()
+                    .name("user_email_idx")
+                    .table(User::Table)
+                    .col(UserEmail::Email)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(PasswordReset::Table)
+                    .if_not_exists()
+                    .col(
+                        big_integer(PasswordReset::Id)
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(big_integer(PasswordReset::UserId))
+                    .col(string_len_uniq(PasswordReset::TokenHash, 64))
+                    .col(
+                        timestamp_with_time_zone(PasswordReset::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(timestamp_with_time_zone(PasswordReset::ExpiresAt))
+                    .col(timestamp_with_time_zone_null(PasswordReset::UsedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_password_reset_user_id")
+                            .from(PasswordReset::Table, PasswordReset::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PasswordReset::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("user_email_idx")
+                    .table(User::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .drop_column(UserEmail::Email)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum UserEmail {
+    Email,
+}
+
+/// 找回密码的一次性令牌，只保存令牌的 SHA3-256 摘要，`used_at` 不为空表示已使用
+#[derive(DeriveIden)]
+pub enum PasswordReset {
+    Table,
+    Id,
+    UserId,
+    TokenHash,
+    CreatedAt,
+    ExpiresAt,
+    UsedAt,
+}
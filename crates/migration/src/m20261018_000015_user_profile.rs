@@ -0,0 +1,79 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::m20261018_000002_create_user::User;
+
+/// 加列时 `created_at` 和 `updated_at` 的占位默认值，之后写入的用户由应用或补齐时间的迁移替换
+pub const PLACEHOLDER_TIMESTAMP: &str = "2000-01-01 00:00:00+00:00";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite 加列时默认值只能是常量，先用常量占位，再把已有用户的时间补为当前时间
+        for column in [
+            string_len_null(UserProfile::Phone, 20),
+            string_len_null(UserProfile::Nickname, 50),
+            string_len_null(UserProfile::Avatar, 255),
+            timestamp_with_time_zone(UserProfile::CreatedAt)
+                .default(PLACEHOLDER_TIMESTAMP)
+                .to_owned(),
+            timestamp_with_time_zone(UserProfile::UpdatedAt)
+                .default(PLACEHOLDER_TIMESTAMP)
+                .to_owned(),
+            timestamp_with_time_zone_null(UserProfile::LastLoginAt),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(User::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(User::Table)
+                    .value(UserProfile::CreatedAt, Expr::current_timestamp())
+                    .value(UserProfile::UpdatedAt, Expr::current_timestamp())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            UserProfile::Phone,
+            UserProfile::Nickname,
+            UserProfile::Avatar,
+            UserProfile::CreatedAt,
+            UserProfile::UpdatedAt,
+            UserProfile::LastLoginAt,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(User::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// 用户资料，`created_at` 和 `updated_at` 由应用写入，`last_login_at` 在登录成功时更新
+#[derive(DeriveIden)]
+pub enum UserProfile {
+    Phone,
+    Nickname,
+    Avatar,
+    CreatedAt,
+    UpdatedAt,
+    LastLoginAt,
+}
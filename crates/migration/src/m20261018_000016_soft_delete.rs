@@ -0,0 +1,54 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::{
+    m20261018_000002_create_user::User, m20261018_000003_create_role::Role,
+    m20261018_000004_create_menu::Menu,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in tables() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .add_column(timestamp_with_time_zone_null(SoftDelete::DeletedAt))
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in tables() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .drop_column(SoftDelete::DeletedAt)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+fn tables() -> [DynIden; 3] {
+    [
+        User::Table.into_iden(),
+        Role::Table.into_iden(),
+        Menu::Table.into_iden(),
+    ]
+}
+
+/// 软删除时间，不为空的记录视为已删除，可以恢复或彻底删除
+#[derive(DeriveIden)]
+pub enum SoftDelete {
+    DeletedAt,
+}
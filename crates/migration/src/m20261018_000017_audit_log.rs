@@ -0,0 +1,73 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 不引用用户表，彻底删除用户后仍保留其审计记录
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuditLog::Table)
+                    .if_not_exists()
+                    .col(big_integer(AuditLog::Id).auto_increment().primary_key())
+                    .col(big_integer_null(AuditLog::ActorId))
+                    .col(string_len(AuditLog::Action, 50))
+                    .col(string_len_null(AuditLog::TargetType, 20))
+                    .col(string_len_null(AuditLog::TargetId, 64))
+                    .col(json_null(AuditLog::Before))
+                    .col(json_null(AuditLog::After))
+                    .col(string_len_null(AuditLog::Ip, 45))
+                    .col(string_len_null(AuditLog::UserAgent, 255))
+                    .col(string_len_null(AuditLog::RequestId, 64))
+                    .col(
+                        timestamp_with_time_zone(AuditLog::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        for (name, columns) in [
+            ("audit_log_actor_id_idx", vec![AuditLog::ActorId]),
+            (
+                "audit_log_target_idx",
+                vec![AuditLog::TargetType, AuditLog::TargetId],
+            ),
+            ("audit_log_created_at_idx", vec![AuditLog::CreatedAt]),
+        ] {
+            let mut index = Index::create();
+            index.name(name).table(AuditLog::Table);
+            for column in columns {
+                index.col(column);
+            }
+            manager.create_index(index.to_owned()).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuditLog::Table).to_owned())
+            .await
+    }
+}
+
+/// 审计日志：`actor_id` 为空表示未登录（例如登录失败），`before` 和 `after` 只包含变化的字段
+#[derive(DeriveIden)]
+pub enum AuditLog {
+    Table,
+    Id,
+    ActorId,
+    Action,
+    TargetType,
+    TargetId,
+    Before,
+    After,
+    Ip,
+    UserAgent,
+    RequestId,
+    CreatedAt,
+}
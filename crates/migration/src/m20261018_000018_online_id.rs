@@ -0,0 +1,134 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use super::{
+    m20261018_000002_create_user::User, m20261018_000005_create_online::Online,
+    m20261018_000012_token_rotation::TokenRotation,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        rebuild(manager, true).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        rebuild(manager, false).await
+    }
+}
+
+/// SQLite 不能修改主键，重建 `online` 表并按创建时间复制已有的会话；
+/// `with_id` 为真时以自增的 `id` 为主键、`token` 唯一，否则恢复以 `token` 为主键
+async fn rebuild(manager: &SchemaManager<'_>, with_id: bool) -> Result<(), DbErr> {
+    manager
+        .rename_table(
+            Table::rename()
+                .table(Online::Table, OnlineId::Previous)
+                .to_owned(),
+        )
+        .await?;
+    // SQLite 和 PostgreSQL 的索引名全库唯一，改名后的旧表仍占用这些名称
+    for name in ["online_expires_at_idx", "online_family_idx"] {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name(name)
+                    .table(OnlineId::Previous)
+                    .to_owned(),
+            )
+            .await?;
+    }
+
+    let mut table = Table::create();
+    table.table(Online::Table);
+    if with_id {
+        table
+            .col(big_integer(OnlineId::Id).auto_increment().primary_key())
+            .col(string_len(Online::Token, 50).unique_key());
+    } else {
+        table.col(string_len(Online::Token, 50).primary_key());
+    }
+    manager
+        .create_table(
+            table
+                .col(big_integer(Online::UserId))
+                .col(timestamp_with_time_zone(Online::CreatedAt).default(Expr::current_timestamp()))
+                .col(
+                    timestamp_with_time_zone(Online::LastSeenAt).default(Expr::current_timestamp()),
+                )
+                .col(timestamp_with_time_zone(Online::ExpiresAt))
+                .col(string_len_null(TokenRotation::Family, 50))
+                .col(timestamp_with_time_zone_null(TokenRotation::RotatedAt))
+                .foreign_key(
+                    ForeignKey::create()
+                        // MySQL 的外键名全库唯一
+                        .name(if with_id {
+                            "fk_online_user"
+                        } else {
+                            "fk_online_user_id"
+                        })
+                        .from(Online::Table, Online::UserId)
+                        .to(User::Table, User::Id),
+                )
+                .to_owned(),
+        )
+        .await?;
+
+    let columns = [
+        Online::Token.into_iden(),
+        Online::UserId.into_iden(),
+        Online::CreatedAt.into_iden(),
+        Online::LastSeenAt.into_iden(),
+        Online::ExpiresAt.into_iden(),
+        TokenRotation::Family.into_iden(),
+        TokenRotation::RotatedAt.into_iden(),
+    ];
+    manager
+        .exec_stmt(
+            Query::insert()
+                .into_table(Online::Table)
+                .columns(columns.clone())
+                .select_from(
+                    Query::select()
+                        .columns(columns)
+                        .from(OnlineId::Previous)
+                        .order_by(Online::CreatedAt, Order::Asc)
+                        .to_owned(),
+                )
+                .map_err(|e| DbErr::Migration(e.to_string()))?
+                .to_owned(),
+        )
+        .await?;
+    manager
+        .drop_table(Table::drop().table(OnlineId::Previous).to_owned())
+        .await?;
+
+    manager
+        .create_index(
+            Index::create()
+                .name("online_expires_at_idx")
+                .table(Online::Table)
+                .col(Online::ExpiresAt)
+                .to_owned(),
+        )
+        .await?;
+    manager
+        .create_index(
+            Index::create()
+                .name("online_family_idx")
+                .table(Online::Table)
+                .col(TokenRotation::Family)
+                .to_owned(),
+        )
+        .await
+}
+
+/// `online.id` 是会话的自增编号，按插入顺序递增，列表和管理接口用它代替令牌标识会话
+#[derive(DeriveIden)]
+pub enum OnlineId {
+    Id,
+    #[sea_orm(iden = "online_previous")]
+    Previous,
+}
@@ -0,0 +1,38 @@
+use sea_orm_migration::{prelude::*, sea_orm::DatabaseBackend};
+
+use super::{
+    m20261018_000002_create_user::User,
+    m20261018_000015_user_profile::{PLACEHOLDER_TIMESTAMP, UserProfile},
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 初始账号在表结构迁移之后写入，创建时间仍是加列时的占位值，补为当前时间；
+        // PostgreSQL 不能直接比较时间戳和文本，SQLite 中时间戳就是文本
+        let placeholder = match manager.get_database_backend() {
+            DatabaseBackend::Postgres => {
+                Expr::val(PLACEHOLDER_TIMESTAMP).cast_as(Alias::new("timestamptz"))
+            }
+            _ => Expr::val(PLACEHOLDER_TIMESTAMP).into(),
+        };
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(User::Table)
+                    .value(UserProfile::CreatedAt, Expr::current_timestamp())
+                    .value(UserProfile::UpdatedAt, Expr::current_timestamp())
+                    .and_where(Expr::col(UserProfile::CreatedAt).eq(placeholder))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // 初始账号由 `m20261018_000009_seed` 回滚时删除
+        Ok(())
+    }
+}